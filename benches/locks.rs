@@ -0,0 +1,109 @@
+// Uncontended lock/unlock latency and contended throughput of the crate's
+// main locks next to std's and parking_lot's Mutex.
+//
+//     cargo bench --bench locks [filter]
+//
+// Contended runs have every thread bump the same counter, so the numbers are
+// lock handoffs rather than useful work. Thread counts above the number of
+// cores are skipped: there the fair locks hand off to waiters that aren't
+// running, and the numbers measure the scheduler instead.
+
+use std::hint::black_box;
+use std::sync::{Barrier, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use spinlock::{McsLock, McsNode, RwSpinLock, SpinLock, TicketLock};
+
+const THREADS: [usize; 4] = [1, 2, 4, 8];
+
+fn uncontended(c: &mut Criterion) {
+    let mut group = c.benchmark_group("uncontended");
+
+    let lock = SpinLock::new(0u64);
+    group.bench_function("SpinLock", |b| b.iter(|| *lock.lock() += black_box(1)));
+    let lock = TicketLock::new(0u64);
+    group.bench_function("TicketLock", |b| b.iter(|| *lock.lock() += black_box(1)));
+    let lock = McsLock::new(0u64);
+    group.bench_function("McsLock", |b| {
+        b.iter(|| *lock.lock(&mut McsNode::new()) += black_box(1))
+    });
+    let lock = RwSpinLock::new(0u64);
+    group.bench_function("RwSpinLock write", |b| {
+        b.iter(|| *lock.write() += black_box(1))
+    });
+    group.bench_function("RwSpinLock read", |b| b.iter(|| black_box(*lock.read())));
+    let lock = Mutex::new(0u64);
+    group.bench_function("std Mutex", |b| {
+        b.iter(|| *lock.lock().unwrap() += black_box(1))
+    });
+    let lock = parking_lot::Mutex::new(0u64);
+    group.bench_function("parking_lot Mutex", |b| {
+        b.iter(|| *lock.lock() += black_box(1))
+    });
+
+    group.finish();
+}
+
+// Runs `f` `iters` times on each of `threads` threads at once, and returns
+// how long the slowest one took.
+fn run(threads: usize, iters: u64, f: &(impl Fn() + Sync)) -> Duration {
+    let start = Barrier::new(threads);
+    thread::scope(|s| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    start.wait();
+                    let started = Instant::now();
+                    for _ in 0..iters {
+                        f();
+                    }
+                    started.elapsed()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .max()
+            .unwrap()
+    })
+}
+
+fn contended(c: &mut Criterion) {
+    let mut group = c.benchmark_group("contended");
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut bench = |name: &str, f: &(dyn Fn() + Sync)| {
+        for threads in THREADS.into_iter().filter(|&n| n <= cores) {
+            // one operation per thread per iteration
+            group.throughput(Throughput::Elements(threads as u64));
+            group.bench_with_input(BenchmarkId::new(name, threads), &threads, |b, &threads| {
+                b.iter_custom(|iters| run(threads, iters, &f))
+            });
+        }
+    };
+
+    let lock = SpinLock::new(0u64);
+    bench("SpinLock", &|| *lock.lock() += black_box(1));
+    let lock = TicketLock::new(0u64);
+    bench("TicketLock", &|| *lock.lock() += black_box(1));
+    let lock = McsLock::new(0u64);
+    bench("McsLock", &|| {
+        *lock.lock(&mut McsNode::new()) += black_box(1)
+    });
+    let lock = RwSpinLock::new(0u64);
+    bench("RwSpinLock write", &|| *lock.write() += black_box(1));
+    bench("RwSpinLock read", &|| {
+        black_box(*lock.read());
+    });
+    let lock = Mutex::new(0u64);
+    bench("std Mutex", &|| *lock.lock().unwrap() += black_box(1));
+    let lock = parking_lot::Mutex::new(0u64);
+    bench("parking_lot Mutex", &|| *lock.lock() += black_box(1));
+
+    group.finish();
+}
+
+criterion_group!(benches, uncontended, contended);
+criterion_main!(benches);
@@ -0,0 +1,25 @@
+// Generates the C header for the `ffi` feature, see src/ffi.rs. It goes to
+// OUT_DIR, so building never touches the source tree; include/spinlock.h is
+// only rewritten with SPINLOCK_REGENERATE_HEADER set.
+fn main() {
+    #[cfg(feature = "ffi")]
+    {
+        let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let out = std::env::var("OUT_DIR").unwrap();
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        println!("cargo:rerun-if-env-changed=SPINLOCK_REGENERATE_HEADER");
+        let config = cbindgen::Config::from_file(format!("{dir}/cbindgen.toml")).unwrap();
+        // only the bindings, the rest of the crate is more than cbindgen
+        // can parse
+        let bindings = cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(format!("{dir}/src/ffi.rs"))
+            .generate()
+            .expect("generating spinlock.h");
+        bindings.write_to_file(format!("{out}/spinlock.h"));
+        if std::env::var_os("SPINLOCK_REGENERATE_HEADER").is_some() {
+            bindings.write_to_file(format!("{dir}/include/spinlock.h"));
+        }
+    }
+}
@@ -0,0 +1,67 @@
+// Compares the crate's TTAS SpinLock against a plain test-and-set lock
+// that writes to the lock's cache line on every spin.
+//
+//     cargo run --release --example contention [threads] [iterations]
+
+use std::cell::UnsafeCell;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, Ordering::Acquire, Ordering::Release};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use spinlock::SpinLock;
+
+struct TasLock {
+    locked: AtomicBool,
+    value: UnsafeCell<u64>,
+}
+
+unsafe impl Sync for TasLock {}
+
+impl TasLock {
+    fn add(&self, n: u64) {
+        while self.locked.swap(true, Acquire) {
+            std::hint::spin_loop();
+        }
+        unsafe { *self.value.get() += n };
+        self.locked.store(false, Release);
+    }
+}
+
+fn run(threads: usize, iterations: u64, f: impl Fn() + Sync) -> Duration {
+    let start = Instant::now();
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                for _ in 0..iterations {
+                    f();
+                }
+            });
+        }
+    });
+    start.elapsed()
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let threads = args
+        .next()
+        .and_then(|a| a.parse().ok())
+        .unwrap_or_else(|| thread::available_parallelism().map_or(4, |n| n.get()));
+    let iterations = args.next().and_then(|a| a.parse().ok()).unwrap_or(200_000);
+
+    let tas = TasLock {
+        locked: AtomicBool::new(false),
+        value: UnsafeCell::new(0),
+    };
+    let tas_time = run(threads, iterations, || tas.add(black_box(1)));
+    assert_eq!(unsafe { *tas.value.get() }, threads as u64 * iterations);
+
+    let ttas = SpinLock::new(0u64);
+    let ttas_time = run(threads, iterations, || *ttas.lock() += black_box(1));
+    assert_eq!(*ttas.lock(), threads as u64 * iterations);
+
+    println!("{threads} threads x {iterations} iterations");
+    println!("test-and-set:          {tas_time:?}");
+    println!("SpinLock (TTAS):       {ttas_time:?}");
+}
@@ -0,0 +1,69 @@
+// The uncontended paths of the main locks as standalone functions, for
+// reading their machine code:
+//
+//     cargo rustc --release --example fast_paths -- --emit asm
+//
+// tests/codegen.rs does that and checks each one stays a handful of
+// instructions with nothing out of line but the `*_slow` paths.
+
+use spinlock::{PhaseFairRwLock, RwSpinLock, SeqLock, SpinLock, SpinSemaphore, TicketLock};
+
+#[no_mangle]
+pub fn spin_lock(lock: &SpinLock<u64>) {
+    *lock.lock() += 1;
+}
+
+#[no_mangle]
+pub fn spin_try_lock(lock: &SpinLock<u64>) -> bool {
+    lock.try_lock().map(|mut value| *value += 1).is_some()
+}
+
+#[no_mangle]
+pub fn ticket_lock(lock: &TicketLock<u64>) {
+    *lock.lock() += 1;
+}
+
+#[no_mangle]
+pub fn rw_read(lock: &RwSpinLock<u64>) -> u64 {
+    *lock.read()
+}
+
+#[no_mangle]
+pub fn rw_write(lock: &RwSpinLock<u64>) {
+    *lock.write() += 1;
+}
+
+#[no_mangle]
+pub fn phase_fair_read(lock: &PhaseFairRwLock<u64>) -> u64 {
+    *lock.read()
+}
+
+#[no_mangle]
+pub fn phase_fair_write(lock: &PhaseFairRwLock<u64>) {
+    *lock.write() += 1;
+}
+
+#[no_mangle]
+pub fn seqlock_read(lock: &SeqLock<u64>) -> u64 {
+    lock.read()
+}
+
+#[no_mangle]
+pub fn semaphore_acquire(semaphore: &SpinSemaphore) {
+    drop(semaphore.acquire());
+}
+
+fn main() {
+    let lock = SpinLock::new(0);
+    spin_lock(&lock);
+    assert!(spin_try_lock(&lock));
+    ticket_lock(&TicketLock::new(0));
+    let rw = RwSpinLock::new(0);
+    rw_write(&rw);
+    assert_eq!(rw_read(&rw), 1);
+    let phase_fair = PhaseFairRwLock::new(0);
+    phase_fair_write(&phase_fair);
+    assert_eq!(phase_fair_read(&phase_fair), 1);
+    assert_eq!(seqlock_read(&SeqLock::new(1)), 1);
+    semaphore_acquire(&SpinSemaphore::new(1));
+}
@@ -0,0 +1,222 @@
+//! Reads the fuzzer's input as a schedule: the first byte picks how many
+//! threads run, the rest are dealt out to them in turn, each byte one
+//! operation on a set of shared locks. Run with
+//!
+//! ```text
+//! cargo +nightly fuzz run schedule
+//! ```
+//!
+//! Each lock protects a pair whose halves its writers keep equal, with a
+//! yield in between, so two holders at once show up as a torn pair. The
+//! totals at the end catch lost updates, and a lock left held after everyone
+//! is done fails `try_lock`.
+#![no_main]
+
+use std::sync::Barrier;
+use std::thread;
+use std::time::Duration;
+
+use libfuzzer_sys::fuzz_target;
+use spinlock::{
+    Guard, MappedGuard, McsGuard, McsLock, McsNode, RwSpinLock, SpinLock, TicketGuard, TicketLock,
+};
+
+const MAX_THREADS: usize = 4;
+// per thread, so a run stays quick
+const MAX_OPS: usize = 64;
+
+const SPIN: usize = 0;
+const TICKET: usize = 1;
+const MCS: usize = 2;
+const RW: usize = 3;
+
+struct Pair(u64, u64);
+
+impl Pair {
+    fn bump(&mut self) {
+        self.0 += 1;
+        thread::yield_now();
+        self.1 += 1;
+    }
+
+    fn check(&self) {
+        assert_eq!(self.0, self.1, "torn pair, two holders at once");
+    }
+}
+
+struct Locks {
+    spin: SpinLock<Pair>,
+    ticket: TicketLock<Pair>,
+    mcs: McsLock<Pair>,
+    rw: RwSpinLock<Pair>,
+}
+
+impl Locks {
+    fn new() -> Self {
+        Self {
+            spin: SpinLock::new(Pair(0, 0)),
+            ticket: TicketLock::new(Pair(0, 0)),
+            mcs: McsLock::new(Pair(0, 0)),
+            rw: RwSpinLock::new(Pair(0, 0)),
+        }
+    }
+}
+
+// Runs one operation, counting the increments it made to each lock's pair.
+fn run(locks: &Locks, op: u8, done: &mut [u64; 4]) {
+    match op % 16 {
+        0 => {
+            locks.spin.lock().bump();
+            done[SPIN] += 1;
+        }
+        1 => {
+            if let Some(mut guard) = locks.spin.try_lock() {
+                assert!(locks.spin.is_locked());
+                assert!(locks.spin.try_lock().is_none());
+                guard.bump();
+                done[SPIN] += 1;
+            }
+        }
+        2 => {
+            let guard = Guard::map(locks.spin.lock(), |pair| pair);
+            let mut guard = MappedGuard::map(guard, |pair| pair);
+            guard.check();
+            guard.bump();
+            done[SPIN] += 1;
+        }
+        3 => {
+            let Err(mut guard) = Guard::try_map(locks.spin.lock(), |_| None::<&mut u64>) else {
+                unreachable!("try_map without a value")
+            };
+            assert!(locks.spin.is_locked());
+            guard.bump();
+            done[SPIN] += 1;
+        }
+        4 => {
+            let mut guard = locks.spin.lock();
+            guard.bump();
+            Guard::bump(&mut guard);
+            guard.check();
+            guard.bump();
+            done[SPIN] += 2;
+        }
+        5 => {
+            let mut guard = locks.spin.lock();
+            guard.bump();
+            Guard::unlocked(&mut guard, thread::yield_now);
+            guard.check();
+            guard.bump();
+            done[SPIN] += 2;
+        }
+        6 => {
+            if let Some(mut guard) = locks.spin.try_lock_for(Duration::from_micros(50)) {
+                guard.bump();
+                done[SPIN] += 1;
+            }
+        }
+        7 => {
+            locks.ticket.lock().bump();
+            done[TICKET] += 1;
+        }
+        8 => {
+            if let Some(mut guard) = locks.ticket.try_lock() {
+                assert!(locks.ticket.try_lock().is_none());
+                guard.bump();
+                done[TICKET] += 1;
+            }
+        }
+        9 => {
+            let mut guard = locks.ticket.lock();
+            guard.bump();
+            TicketGuard::bump(&mut guard);
+            guard.check();
+            guard.bump();
+            guard.unlock_fair();
+            done[TICKET] += 2;
+        }
+        10 => {
+            let mut node = McsNode::new();
+            let mut guard = match locks.mcs.try_lock(&mut node) {
+                Some(guard) => guard,
+                None => return,
+            };
+            guard.bump();
+            guard.unlock_fair();
+            done[MCS] += 1;
+        }
+        11 => {
+            let mut node = McsNode::new();
+            let mut guard = locks.mcs.lock(&mut node);
+            guard.bump();
+            McsGuard::bump(&mut guard);
+            guard.check();
+            guard.bump();
+            done[MCS] += 2;
+        }
+        12 => locks.rw.read().check(),
+        13 => {
+            let mut guard = locks.rw.write();
+            guard.bump();
+            guard.downgrade().check();
+            done[RW] += 1;
+        }
+        14 => {
+            let guard = locks.rw.upgradeable_read();
+            guard.check();
+            guard.upgrade().bump();
+            done[RW] += 1;
+        }
+        _ => {
+            if let Some(mut guard) = locks.rw.try_write() {
+                assert!(locks.rw.try_read().is_none());
+                guard.bump();
+                done[RW] += 1;
+            } else if let Some(guard) = locks.rw.try_read() {
+                guard.check();
+            }
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&threads, ops)) = data.split_first() else {
+        return;
+    };
+    let threads = threads as usize % (MAX_THREADS - 1) + 2;
+    let locks = Locks::new();
+    let start = Barrier::new(threads);
+
+    let mut done = [0; 4];
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|i| {
+                let (locks, start) = (&locks, &start);
+                let ops = ops.iter().skip(i).step_by(threads).take(MAX_OPS);
+                scope.spawn(move || {
+                    let mut done = [0; 4];
+                    start.wait();
+                    for &op in ops {
+                        run(locks, op, &mut done);
+                    }
+                    done
+                })
+            })
+            .collect();
+        for handle in handles {
+            let counts = handle.join().unwrap();
+            for (total, count) in done.iter_mut().zip(counts) {
+                *total += count;
+            }
+        }
+    });
+
+    let spin = locks.spin.try_lock().expect("SpinLock left locked");
+    let ticket = locks.ticket.try_lock().expect("TicketLock left locked");
+    let mut node = McsNode::new();
+    let mcs = locks.mcs.try_lock(&mut node).expect("McsLock left locked");
+    let rw = locks.rw.try_write().expect("RwSpinLock left locked");
+    for (i, pair) in [&*spin, &*ticket, &*mcs, &*rw].into_iter().enumerate() {
+        pair.check();
+        assert_eq!(pair.0, done[i], "lost update");
+    }
+});
@@ -0,0 +1,61 @@
+#ifndef SPINLOCK_H
+#define SPINLOCK_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The lock behind a C `spinlock_t *`.
+ */
+typedef struct spinlock_t spinlock_t;
+
+/**
+ * Allocates a new unlocked lock.
+ */
+struct spinlock_t *spinlock_new(void);
+
+/**
+ * Frees a lock made by `spinlock_new`. NULL is ignored.
+ *
+ * # Safety
+ *
+ * `lock` must be NULL or come from `spinlock_new`, not be locked, and not
+ * be used by anyone after this.
+ */
+void spinlock_destroy(struct spinlock_t *lock);
+
+/**
+ * Spins until the lock is ours.
+ *
+ * # Safety
+ *
+ * `lock` must come from `spinlock_new` and not be destroyed yet.
+ */
+void spinlock_lock(const struct spinlock_t *lock);
+
+/**
+ * Takes the lock if it's free right now, never spins. Returns whether it
+ * did.
+ *
+ * # Safety
+ *
+ * `lock` must come from `spinlock_new` and not be destroyed yet.
+ */
+bool spinlock_try_lock(const struct spinlock_t *lock);
+
+/**
+ * Releases the lock.
+ *
+ * # Safety
+ *
+ * `lock` must come from `spinlock_new`, not be destroyed yet, and be held
+ * by the caller, from `spinlock_lock` or a successful `spinlock_try_lock`.
+ */
+void spinlock_unlock(const struct spinlock_t *lock);
+
+#endif  /* SPINLOCK_H */
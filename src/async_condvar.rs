@@ -0,0 +1,150 @@
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use crate::waiters::WaitQueue;
+use crate::{AsyncMutex, AsyncMutexGuard, LockFuture, SpinLock};
+
+/// Condition variable for `AsyncMutex`: `wait(guard).await` unlocks the
+/// mutex, parks the task until notified and locks it again. Notified tasks
+/// are woken in the order they started waiting; as with any condvar, wait
+/// in a loop over the actual condition. Works with any executor.
+///
+/// ```
+/// use spinlock::{AsyncCondvar, AsyncMutex};
+///
+/// let state = (AsyncMutex::new(Vec::new()), AsyncCondvar::new());
+/// std::thread::scope(|s| {
+///     s.spawn(|| {
+///         futures::executor::block_on(async {
+///             state.0.lock().await.push(1);
+///             state.1.notify_one();
+///         })
+///     });
+///     futures::executor::block_on(async {
+///         let mut items = state.0.lock().await;
+///         while items.is_empty() {
+///             items = state.1.wait(items).await;
+///         }
+///         assert_eq!(items.pop(), Some(1));
+///     });
+/// });
+/// ```
+pub struct AsyncCondvar {
+    waiters: SpinLock<WaitQueue>,
+}
+
+impl AsyncCondvar {
+    pub const fn new() -> Self {
+        Self {
+            waiters: SpinLock::new(WaitQueue::new()),
+        }
+    }
+
+    /// Unlocks the guard's mutex, waits for a notification and locks it
+    /// again. The task is queued before the mutex is unlocked, so a notify
+    /// made under the mutex after that can't be missed.
+    pub fn wait<'a, T>(&'a self, guard: AsyncMutexGuard<'a, T>) -> WaitFuture<'a, T> {
+        WaitFuture {
+            condvar: self,
+            lock: guard.lock,
+            key: None,
+            state: State::Unlocking(guard),
+        }
+    }
+
+    pub fn notify_one(&self) {
+        let next = self.waiters.lock().pop();
+        if let Some(waker) = next {
+            waker.wake();
+        }
+    }
+
+    pub fn notify_all(&self) {
+        let mut waiters = self.waiters.lock().take();
+        while let Some(waker) = waiters.pop() {
+            waker.wake();
+        }
+    }
+}
+
+impl Default for AsyncCondvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+enum State<'a, T> {
+    // not polled yet, still holding the mutex
+    Unlocking(AsyncMutexGuard<'a, T>),
+    // queued on the condvar
+    Waiting,
+    // notified, taking the mutex again
+    Locking(LockFuture<'a, T>),
+}
+
+/// Future returned by `AsyncCondvar::wait`. Dropping it while waiting takes
+/// it off the queue, passing on a `notify_one` it already received; dropped
+/// before its first poll, it just unlocks the mutex.
+pub struct WaitFuture<'a, T> {
+    condvar: &'a AsyncCondvar,
+    lock: &'a AsyncMutex<T>,
+    key: Option<usize>,
+    state: State<'a, T>,
+}
+
+impl<'a, T> Future for WaitFuture<'a, T> {
+    type Output = AsyncMutexGuard<'a, T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            match &mut this.state {
+                State::Unlocking(_) => {
+                    this.condvar
+                        .waiters
+                        .lock()
+                        .register(&mut this.key, cx.waker());
+                    // unlocks only now that we're queued
+                    if let State::Unlocking(guard) = mem::replace(&mut this.state, State::Waiting) {
+                        drop(guard);
+                    }
+                    return Poll::Pending;
+                }
+                State::Waiting => {
+                    let mut waiters = this.condvar.waiters.lock();
+                    // still queued means nobody notified us yet
+                    if this.key.is_some_and(|id| waiters.contains(id)) {
+                        waiters.register(&mut this.key, cx.waker());
+                        return Poll::Pending;
+                    }
+                    drop(waiters);
+                    this.key = None;
+                    this.state = State::Locking(this.lock.lock());
+                }
+                State::Locking(relock) => return Pin::new(relock).poll(cx),
+            }
+        }
+    }
+}
+
+impl<T> Drop for WaitFuture<'_, T> {
+    fn drop(&mut self) {
+        let (State::Waiting, Some(id)) = (&self.state, self.key) else {
+            return;
+        };
+        let next = {
+            let mut waiters = self.condvar.waiters.lock();
+            if waiters.remove(id) {
+                return;
+            }
+            // A notify_one picked us and we're going away without using it,
+            // so the next waiter has to get it instead.
+            waiters.pop()
+        };
+        if let Some(waker) = next {
+            waker.wake();
+        }
+    }
+}
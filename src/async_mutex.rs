@@ -0,0 +1,156 @@
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::pin::Pin;
+use core::sync::atomic::AtomicBool;
+use core::task::{Context, Poll};
+
+use crate::sync::{Acquire, Relaxed, Release};
+use crate::waiters::WaitQueue;
+use crate::{timeout, SpinLock, Timeout};
+
+/// Mutex for async code: `lock()` returns a future that parks the task (by
+/// registering its `Waker`) instead of spinning on the executor thread, and
+/// dropping the guard wakes the next waiting task. Works with any executor.
+pub struct AsyncMutex<T> {
+    locked: AtomicBool,
+    waiters: SpinLock<WaitQueue>,
+    pub(crate) value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for AsyncMutex<T> where T: Send {}
+
+impl<T> AsyncMutex<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            waiters: SpinLock::new(WaitQueue::new()),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn lock(&self) -> LockFuture<'_, T> {
+        LockFuture {
+            lock: self,
+            key: None,
+        }
+    }
+
+    /// `lock` that gives up once `sleep` completes, see `timeout`. None
+    /// means it timed out.
+    pub fn lock_timeout<S: Future>(&self, sleep: S) -> Timeout<LockFuture<'_, T>, S> {
+        timeout(self.lock(), sleep)
+    }
+
+    pub fn try_lock(&self) -> Option<AsyncMutexGuard<'_, T>> {
+        self.acquire().then(|| AsyncMutexGuard {
+            lock: self,
+            marker: PhantomData,
+        })
+    }
+
+    #[inline]
+    fn acquire(&self) -> bool {
+        self.locked
+            .compare_exchange(false, true, Acquire, Relaxed)
+            .is_ok()
+    }
+}
+
+/// Future returned by `AsyncMutex::lock`. Dropping it before it completes
+/// (e.g. losing a `select!`) takes it off the wait queue, passing on a
+/// wakeup it already received.
+pub struct LockFuture<'a, T> {
+    lock: &'a AsyncMutex<T>,
+    key: Option<usize>,
+}
+
+impl<'a, T> Future for LockFuture<'a, T> {
+    type Output = AsyncMutexGuard<'a, T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let lock = self.lock;
+        if lock.acquire() {
+            // polled again for some other reason and got lucky, don't leave a
+            // stale entry behind for the next unlock to waste its wakeup on
+            if let Some(id) = self.key.take() {
+                lock.waiters.lock().remove(id);
+            }
+            return Poll::Ready(AsyncMutexGuard {
+                lock,
+                marker: PhantomData,
+            });
+        }
+        let mut waiters = lock.waiters.lock();
+        // Re-check with the queue locked: an unlock either happened before
+        // this and we get the lock now, or it will find us in the queue.
+        if lock.acquire() {
+            if let Some(id) = self.key.take() {
+                waiters.remove(id);
+            }
+            return Poll::Ready(AsyncMutexGuard {
+                lock,
+                marker: PhantomData,
+            });
+        }
+        waiters.register(&mut self.key, cx.waker());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for LockFuture<'_, T> {
+    fn drop(&mut self) {
+        let Some(id) = self.key else {
+            return;
+        };
+        let next = {
+            let mut waiters = self.lock.waiters.lock();
+            if waiters.remove(id) {
+                return;
+            }
+            // An unlock woke us and we're going away without using it, so the
+            // next waiter has to get that wakeup instead.
+            waiters.pop()
+        };
+        if let Some(waker) = next {
+            waker.wake();
+        }
+    }
+}
+
+pub struct AsyncMutexGuard<'a, T> {
+    pub(crate) lock: &'a AsyncMutex<T>,
+    // Send and Sync are implemented below rather than following `lock`
+    pub(crate) marker: PhantomData<*const ()>,
+}
+
+unsafe impl<T: Send> Send for AsyncMutexGuard<'_, T> {}
+unsafe impl<T: Sync> Sync for AsyncMutexGuard<'_, T> {}
+
+impl<T> Deref for AsyncMutexGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: very existence of guard
+        // guarantees we've exclusively locked the lock
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for AsyncMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: very existence of guard
+        // guarantees we've exclusively locked the lock
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for AsyncMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Release);
+        let next = self.lock.waiters.lock().pop();
+        if let Some(waker) = next {
+            waker.wake();
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::ops::{Deref, DerefMut};
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use crate::waiters::WaitQueue;
+use crate::{timeout, SpinLock, Timeout};
+
+// Waiters are never woken to retry: whoever unlocks takes them off the queue
+// and hands them the lock right away, so a future that finds itself gone
+// from its queue already owns its share of the lock.
+struct State {
+    readers: usize,
+    writer: bool,
+    read_queue: WaitQueue,
+    write_queue: WaitQueue,
+}
+
+/// Reader-writer lock for async code, for any executor. Neither side can
+/// starve the other: new readers queue up behind a waiting writer, and a
+/// writer releasing the lock lets in every reader that queued meanwhile
+/// before the next writer.
+pub struct AsyncRwLock<T> {
+    state: SpinLock<State>,
+    pub(crate) value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for AsyncRwLock<T> where T: Send + Sync {}
+
+impl<T> AsyncRwLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            state: SpinLock::new(State {
+                readers: 0,
+                writer: false,
+                read_queue: WaitQueue::new(),
+                write_queue: WaitQueue::new(),
+            }),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn read(&self) -> ReadFuture<'_, T> {
+        ReadFuture {
+            lock: self,
+            key: None,
+        }
+    }
+
+    pub fn write(&self) -> WriteFuture<'_, T> {
+        WriteFuture {
+            lock: self,
+            key: None,
+        }
+    }
+
+    /// `read` and `write` that give up once `sleep` completes, see
+    /// `timeout`. None means they timed out.
+    pub fn read_timeout<S: Future>(&self, sleep: S) -> Timeout<ReadFuture<'_, T>, S> {
+        timeout(self.read(), sleep)
+    }
+
+    pub fn write_timeout<S: Future>(&self, sleep: S) -> Timeout<WriteFuture<'_, T>, S> {
+        timeout(self.write(), sleep)
+    }
+
+    pub fn try_read(&self) -> Option<AsyncReadGuard<'_, T>> {
+        let mut state = self.state.lock();
+        if state.writer || !state.write_queue.is_empty() {
+            return None;
+        }
+        state.readers += 1;
+        Some(AsyncReadGuard { lock: self })
+    }
+
+    pub fn try_write(&self) -> Option<AsyncWriteGuard<'_, T>> {
+        let mut state = self.state.lock();
+        if state.writer || state.readers != 0 {
+            return None;
+        }
+        state.writer = true;
+        Some(AsyncWriteGuard { lock: self })
+    }
+
+    fn unlock_read(&self) {
+        let next = {
+            let mut state = self.state.lock();
+            state.readers -= 1;
+            if state.readers == 0 {
+                state.grant_writer()
+            } else {
+                None
+            }
+        };
+        if let Some(waker) = next {
+            waker.wake();
+        }
+    }
+
+    fn unlock_write(&self) {
+        let (mut readers, writer) = {
+            let mut state = self.state.lock();
+            state.writer = false;
+            if state.read_queue.is_empty() {
+                (WaitQueue::new(), state.grant_writer())
+            } else {
+                state.readers += state.read_queue.len();
+                (state.read_queue.take(), None)
+            }
+        };
+        while let Some(waker) = readers.pop() {
+            waker.wake();
+        }
+        if let Some(waker) = writer {
+            waker.wake();
+        }
+    }
+}
+
+impl State {
+    fn grant_writer(&mut self) -> Option<core::task::Waker> {
+        let waker = self.write_queue.pop()?;
+        self.writer = true;
+        Some(waker)
+    }
+}
+
+/// Future returned by `AsyncRwLock::read`. Like `WriteFuture`, dropping it
+/// before completion leaves the queue, and gives the lock back if it had
+/// already been handed over.
+pub struct ReadFuture<'a, T> {
+    lock: &'a AsyncRwLock<T>,
+    key: Option<usize>,
+}
+
+impl<'a, T> Future for ReadFuture<'a, T> {
+    type Output = AsyncReadGuard<'a, T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let lock = self.lock;
+        let mut state = lock.state.lock();
+        match self.key {
+            // handed the lock while we were queued
+            Some(id) if !state.read_queue.contains(id) => {
+                self.key = None;
+                return Poll::Ready(AsyncReadGuard { lock });
+            }
+            Some(_) => {}
+            None if !state.writer && state.write_queue.is_empty() => {
+                state.readers += 1;
+                return Poll::Ready(AsyncReadGuard { lock });
+            }
+            None => {}
+        }
+        state.read_queue.register(&mut self.key, cx.waker());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for ReadFuture<'_, T> {
+    fn drop(&mut self) {
+        let Some(id) = self.key else {
+            return;
+        };
+        let granted = !self.lock.state.lock().read_queue.remove(id);
+        if granted {
+            // we were handed a share of the lock but never used it
+            self.lock.unlock_read();
+        }
+    }
+}
+
+/// Future returned by `AsyncRwLock::write`.
+pub struct WriteFuture<'a, T> {
+    lock: &'a AsyncRwLock<T>,
+    key: Option<usize>,
+}
+
+impl<'a, T> Future for WriteFuture<'a, T> {
+    type Output = AsyncWriteGuard<'a, T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let lock = self.lock;
+        let mut state = lock.state.lock();
+        match self.key {
+            // handed the lock while we were queued
+            Some(id) if !state.write_queue.contains(id) => {
+                self.key = None;
+                return Poll::Ready(AsyncWriteGuard { lock });
+            }
+            Some(_) => {}
+            None if !state.writer && state.readers == 0 && state.write_queue.is_empty() => {
+                state.writer = true;
+                return Poll::Ready(AsyncWriteGuard { lock });
+            }
+            None => {}
+        }
+        state.write_queue.register(&mut self.key, cx.waker());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for WriteFuture<'_, T> {
+    fn drop(&mut self) {
+        let Some(id) = self.key else {
+            return;
+        };
+        let mut readers = {
+            let mut state = self.lock.state.lock();
+            if !state.write_queue.remove(id) {
+                drop(state);
+                // we were handed the lock but never used it
+                self.lock.unlock_write();
+                return;
+            }
+            // readers that queued only because we were waiting would
+            // otherwise wait for an unlock_write that never comes
+            if !state.writer && state.write_queue.is_empty() {
+                state.readers += state.read_queue.len();
+                state.read_queue.take()
+            } else {
+                WaitQueue::new()
+            }
+        };
+        while let Some(waker) = readers.pop() {
+            waker.wake();
+        }
+    }
+}
+
+pub struct AsyncReadGuard<'a, T> {
+    pub(crate) lock: &'a AsyncRwLock<T>,
+}
+
+impl<T> Deref for AsyncReadGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: no writer can get in while this read guard is alive
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for AsyncReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.unlock_read();
+    }
+}
+
+pub struct AsyncWriteGuard<'a, T> {
+    pub(crate) lock: &'a AsyncRwLock<T>,
+}
+
+impl<T> Deref for AsyncWriteGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: the writer flag excludes every other guard
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for AsyncWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: the writer flag excludes every other guard
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for AsyncWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.unlock_write();
+    }
+}
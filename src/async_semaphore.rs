@@ -0,0 +1,305 @@
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use crate::SpinLock;
+
+// Like AsyncRwLock, waiters are never woken to retry: whoever returns permits
+// takes waiters off the front of the queue and hands them theirs, so a
+// future that finds itself gone from the queue already owns its permits.
+struct State {
+    permits: usize,
+    // (key, permits wanted, waker), served strictly in order
+    queue: VecDeque<(usize, usize, Waker)>,
+    next_id: usize,
+}
+
+impl State {
+    // Hands out permits to the front of the queue while they last. The
+    // caller wakes the returned wakers once it has let go of the state.
+    fn grant(&mut self) -> Vec<Waker> {
+        let mut woken = Vec::new();
+        while let Some(&(_, wanted, _)) = self.queue.front() {
+            if wanted > self.permits {
+                break;
+            }
+            self.permits -= wanted;
+            if let Some((_, _, waker)) = self.queue.pop_front() {
+                woken.push(waker);
+            }
+        }
+        woken
+    }
+}
+
+/// Counting semaphore for async code, for any executor, to bound how many
+/// tasks do something at once (connections from a pool, requests in flight).
+/// Waiting tasks are served in order, so one asking for many permits isn't
+/// starved by a stream of tasks asking for one, but it does hold up the
+/// tasks behind it until its permits are free.
+///
+/// ```
+/// use spinlock::AsyncSemaphore;
+///
+/// let connections = AsyncSemaphore::new(2);
+/// futures::executor::block_on(async {
+///     let _a = connections.acquire().await;
+///     let _b = connections.acquire().await;
+///     assert!(connections.try_acquire().is_none());
+/// });
+/// assert_eq!(connections.available_permits(), 2);
+/// ```
+pub struct AsyncSemaphore {
+    state: SpinLock<State>,
+}
+
+impl AsyncSemaphore {
+    pub const fn new(permits: usize) -> Self {
+        Self {
+            state: SpinLock::new(State {
+                permits,
+                queue: VecDeque::new(),
+                next_id: 0,
+            }),
+        }
+    }
+
+    pub fn acquire(&self) -> AcquireFuture<'_> {
+        self.acquire_many(1)
+    }
+
+    /// Waits for `n` permits at once. Asking for more than the semaphore
+    /// will ever have waits forever.
+    pub fn acquire_many(&self, n: usize) -> AcquireFuture<'_> {
+        AcquireFuture {
+            semaphore: self,
+            n,
+            key: None,
+        }
+    }
+
+    /// `acquire` for a permit that keeps its own `Arc` to the semaphore, so
+    /// it's `'static` and can be moved into spawned tasks.
+    pub fn acquire_owned(self: &Arc<Self>) -> OwnedAcquireFuture {
+        self.acquire_many_owned(1)
+    }
+
+    pub fn acquire_many_owned(self: &Arc<Self>, n: usize) -> OwnedAcquireFuture {
+        OwnedAcquireFuture {
+            semaphore: self.clone(),
+            n,
+            key: None,
+        }
+    }
+
+    // Fails if there are too few permits, or tasks already waiting for them.
+    pub fn try_acquire(&self) -> Option<AsyncPermit<'_>> {
+        self.try_acquire_many(1)
+    }
+
+    pub fn try_acquire_many(&self, n: usize) -> Option<AsyncPermit<'_>> {
+        self.take(n).then(|| AsyncPermit { semaphore: self, n })
+    }
+
+    pub fn try_acquire_owned(self: &Arc<Self>) -> Option<OwnedPermit> {
+        self.take(1).then(|| OwnedPermit {
+            semaphore: self.clone(),
+            n: 1,
+        })
+    }
+
+    /// Only a snapshot, other tasks may take or return permits right after.
+    pub fn available_permits(&self) -> usize {
+        self.state.lock().permits
+    }
+
+    /// Adds `n` permits. Permits go back on their own when dropped, so this
+    /// is for growing the pool or returning permits that were `forget`-ed.
+    pub fn add_permits(&self, n: usize) {
+        let woken = {
+            let mut state = self.state.lock();
+            state.permits += n;
+            state.grant()
+        };
+        for waker in woken {
+            waker.wake();
+        }
+    }
+
+    fn take(&self, n: usize) -> bool {
+        let mut state = self.state.lock();
+        if !state.queue.is_empty() || state.permits < n {
+            return false;
+        }
+        state.permits -= n;
+        true
+    }
+
+    // The shared part of both acquire futures. Ready means `n` permits are
+    // ours, and `key` is cleared so dropping the future won't return them.
+    fn poll_acquire(&self, n: usize, key: &mut Option<usize>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.state.lock();
+        match *key {
+            Some(id) => match state.queue.iter_mut().find(|(i, _, _)| *i == id) {
+                Some((_, _, waker)) => {
+                    if !waker.will_wake(cx.waker()) {
+                        *waker = cx.waker().clone();
+                    }
+                    Poll::Pending
+                }
+                None => {
+                    *key = None;
+                    Poll::Ready(())
+                }
+            },
+            None => {
+                if state.queue.is_empty() && state.permits >= n {
+                    state.permits -= n;
+                    return Poll::Ready(());
+                }
+                let id = state.next_id;
+                state.next_id = id.wrapping_add(1);
+                state.queue.push_back((id, n, cx.waker().clone()));
+                *key = Some(id);
+                Poll::Pending
+            }
+        }
+    }
+
+    // A pending acquire going away: off the queue, or if it was already
+    // granted its permits, back they go.
+    fn cancel(&self, n: usize, id: usize) {
+        let woken = {
+            let mut state = self.state.lock();
+            match state.queue.iter().position(|(i, _, _)| *i == id) {
+                // the tasks behind it may fit in what it was waiting for
+                Some(pos) => {
+                    state.queue.remove(pos);
+                }
+                None => state.permits += n,
+            }
+            state.grant()
+        };
+        for waker in woken {
+            waker.wake();
+        }
+    }
+}
+
+/// Future returned by `AsyncSemaphore::acquire` and `acquire_many`.
+/// Dropping it before it completes takes it off the queue, returning any
+/// permits it was already handed.
+pub struct AcquireFuture<'a> {
+    semaphore: &'a AsyncSemaphore,
+    n: usize,
+    key: Option<usize>,
+}
+
+impl<'a> Future for AcquireFuture<'a> {
+    type Output = AsyncPermit<'a>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let semaphore = this.semaphore;
+        semaphore
+            .poll_acquire(this.n, &mut this.key, cx)
+            .map(|()| AsyncPermit {
+                semaphore,
+                n: this.n,
+            })
+    }
+}
+
+impl Drop for AcquireFuture<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.key {
+            self.semaphore.cancel(self.n, id);
+        }
+    }
+}
+
+/// Future returned by `AsyncSemaphore::acquire_owned`, see `AcquireFuture`.
+pub struct OwnedAcquireFuture {
+    semaphore: Arc<AsyncSemaphore>,
+    n: usize,
+    key: Option<usize>,
+}
+
+impl Future for OwnedAcquireFuture {
+    type Output = OwnedPermit;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.semaphore
+            .poll_acquire(this.n, &mut this.key, cx)
+            .map(|()| OwnedPermit {
+                semaphore: this.semaphore.clone(),
+                n: this.n,
+            })
+    }
+}
+
+impl Drop for OwnedAcquireFuture {
+    fn drop(&mut self) {
+        if let Some(id) = self.key {
+            self.semaphore.cancel(self.n, id);
+        }
+    }
+}
+
+/// Permits from an `AsyncSemaphore`, handed back when dropped.
+pub struct AsyncPermit<'a> {
+    semaphore: &'a AsyncSemaphore,
+    n: usize,
+}
+
+impl AsyncPermit<'_> {
+    pub fn num_permits(&self) -> usize {
+        self.n
+    }
+
+    /// Keeps the permits taken for good, see `AsyncSemaphore::add_permits`.
+    pub fn forget(mut self) {
+        self.n = 0;
+    }
+}
+
+impl Drop for AsyncPermit<'_> {
+    fn drop(&mut self) {
+        if self.n != 0 {
+            self.semaphore.add_permits(self.n);
+        }
+    }
+}
+
+/// `AsyncPermit` holding its own `Arc` to the semaphore.
+pub struct OwnedPermit {
+    semaphore: Arc<AsyncSemaphore>,
+    n: usize,
+}
+
+impl OwnedPermit {
+    pub fn num_permits(&self) -> usize {
+        self.n
+    }
+
+    pub fn semaphore(&self) -> &Arc<AsyncSemaphore> {
+        &self.semaphore
+    }
+
+    /// Keeps the permits taken for good, see `AsyncSemaphore::add_permits`.
+    pub fn forget(mut self) {
+        self.n = 0;
+    }
+}
+
+impl Drop for OwnedPermit {
+    fn drop(&mut self) {
+        if self.n != 0 {
+            self.semaphore.add_permits(self.n);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::{self, ManuallyDrop};
+use core::ptr;
+#[cfg(target_has_atomic = "16")]
+use core::sync::atomic::AtomicU16;
+#[cfg(target_has_atomic = "32")]
+use core::sync::atomic::AtomicU32;
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::AtomicU64;
+#[cfg(target_has_atomic = "8")]
+use core::sync::atomic::AtomicU8;
+use core::sync::atomic::Ordering::SeqCst;
+
+use crate::{CachePadded, RawSpinLock};
+
+/// A `Cell` that's safe to share between threads. Types with the size and
+/// alignment of a native atomic integer go through that atomic; anything
+/// else takes one of a fixed set of spinlocks picked by the cell's address.
+pub struct AtomicCell<T> {
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for AtomicCell<T> {}
+unsafe impl<T: Send> Sync for AtomicCell<T> {}
+
+// Runs `$native` with `$atomic` bound to the cell's value viewed as the first
+// atomic type that fits T exactly, or `$fallback` if none does.
+macro_rules! atomic {
+    (@check $t:ty, $ptr:expr, $atomic:ident, $a:ty, $native:expr) => {
+        if fits::<$t, $a>() {
+            // safety: same size, and T is at least as aligned as the atomic
+            let $atomic = unsafe { &*($ptr as *const $a) };
+            break $native;
+        }
+    };
+    ($t:ty, $ptr:expr, $atomic:ident, $native:expr, $fallback:expr) => {
+        loop {
+            #[cfg(target_has_atomic = "8")]
+            atomic!(@check $t, $ptr, $atomic, AtomicU8, $native);
+            #[cfg(target_has_atomic = "16")]
+            atomic!(@check $t, $ptr, $atomic, AtomicU16, $native);
+            #[cfg(target_has_atomic = "32")]
+            atomic!(@check $t, $ptr, $atomic, AtomicU32, $native);
+            #[cfg(target_has_atomic = "64")]
+            atomic!(@check $t, $ptr, $atomic, AtomicU64, $native);
+            break $fallback;
+        }
+    };
+}
+
+const fn fits<T, A>() -> bool {
+    mem::size_of::<T>() == mem::size_of::<A>() && mem::align_of::<T>() >= mem::align_of::<A>()
+}
+
+impl<T> AtomicCell<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    pub fn as_ptr(&self) -> *mut T {
+        self.value.get()
+    }
+
+    /// Whether operations on this `T` use a native atomic rather than a lock.
+    pub const fn is_lock_free() -> bool {
+        #[cfg(target_has_atomic = "8")]
+        if fits::<T, AtomicU8>() {
+            return true;
+        }
+        #[cfg(target_has_atomic = "16")]
+        if fits::<T, AtomicU16>() {
+            return true;
+        }
+        #[cfg(target_has_atomic = "32")]
+        if fits::<T, AtomicU32>() {
+            return true;
+        }
+        #[cfg(target_has_atomic = "64")]
+        if fits::<T, AtomicU64>() {
+            return true;
+        }
+        false
+    }
+
+    pub fn store(&self, value: T) {
+        drop(self.swap(value));
+    }
+
+    pub fn swap(&self, value: T) -> T {
+        let value = ManuallyDrop::new(value);
+        atomic! {
+            T, self.value.get(), atomic,
+            // safety: the bits of a T going in and coming out, ownership
+            // moving along with them
+            unsafe { mem::transmute_copy(&atomic.swap(mem::transmute_copy(&value), SeqCst)) },
+            {
+                let _stripe = Stripe::lock(self.as_ptr());
+                // safety: the stripe lock serializes all access to the value
+                unsafe { ptr::replace(self.value.get(), ManuallyDrop::into_inner(value)) }
+            }
+        }
+    }
+}
+
+impl<T: Copy> AtomicCell<T> {
+    pub fn load(&self) -> T {
+        atomic! {
+            T, self.value.get(), atomic,
+            // safety: the bits of a T
+            unsafe { mem::transmute_copy(&atomic.load(SeqCst)) },
+            {
+                let _stripe = Stripe::lock(self.as_ptr());
+                // safety: the stripe lock serializes all access to the value
+                unsafe { ptr::read(self.value.get()) }
+            }
+        }
+    }
+}
+
+impl<T: Copy + Eq> AtomicCell<T> {
+    /// Stores `new` if the value is `== current`, returning the previous
+    /// value either way: `Ok` if it was swapped, `Err` if not.
+    pub fn compare_exchange(&self, mut current: T, new: T) -> Result<T, T> {
+        atomic! {
+            T, self.value.get(), atomic,
+            loop {
+                // safety: the bits of Ts, all Copy
+                let result = unsafe {
+                    atomic.compare_exchange(
+                        mem::transmute_copy(&current),
+                        mem::transmute_copy(&new),
+                        SeqCst,
+                        SeqCst,
+                    )
+                };
+                match result {
+                    Ok(_) => break Ok(current),
+                    Err(previous) => {
+                        // safety: the bits of a T
+                        let previous: T = unsafe { mem::transmute_copy(&previous) };
+                        // equal but with different bits, like 0.0 and -0.0 in
+                        // a float wrapper: retry with the bits actually stored
+                        if previous != current {
+                            break Err(previous);
+                        }
+                        current = previous;
+                    }
+                }
+            },
+            {
+                let _stripe = Stripe::lock(self.as_ptr());
+                // safety: the stripe lock serializes all access to the value
+                let previous = unsafe { ptr::read(self.value.get()) };
+                if previous == current {
+                    unsafe { ptr::write(self.value.get(), new) };
+                    Ok(previous)
+                } else {
+                    Err(previous)
+                }
+            }
+        }
+    }
+}
+
+impl<T: Default> AtomicCell<T> {
+    pub fn take(&self) -> T {
+        self.swap(T::default())
+    }
+}
+
+impl<T: Default> Default for AtomicCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for AtomicCell<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for AtomicCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AtomicCell")
+            .field("value", &self.load())
+            .finish()
+    }
+}
+
+// Prime, so cells at regularly spaced addresses still spread out.
+const STRIPES: usize = 67;
+
+static LOCKS: [CachePadded<RawSpinLock>; STRIPES] =
+    [const { CachePadded::new(RawSpinLock::new()) }; STRIPES];
+
+// One lock of the pool, held for as long as it lives.
+struct Stripe(&'static RawSpinLock);
+
+impl Stripe {
+    fn lock<T>(addr: *const T) -> Self {
+        let lock = &LOCKS[addr as usize % STRIPES];
+        lock.lock();
+        Self(lock)
+    }
+}
+
+impl Drop for Stripe {
+    fn drop(&mut self) {
+        // safety: taken in Stripe::lock
+        unsafe { self.0.unlock() }
+    }
+}
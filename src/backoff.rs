@@ -0,0 +1,210 @@
+use core::sync::atomic::AtomicU32;
+
+use crate::sync::{spin_loop, Relaxed};
+
+// Shared seed source so backoffs started at the same time don't draw the same jitter.
+static SEED: AtomicU32 = AtomicU32::new(0x9e37_79b9);
+
+// The current `BackoffConfig`, with u32::MAX for None
+static MAX_SPINS: AtomicU32 = AtomicU32::new(BackoffConfig::DEFAULT.max_spins);
+static YIELD_AFTER: AtomicU32 = AtomicU32::new(encode(BackoffConfig::DEFAULT.yield_after));
+static SLEEP_AFTER: AtomicU32 = AtomicU32::new(encode(BackoffConfig::DEFAULT.sleep_after));
+
+// The first sleep, each one after that doubles up to the last
+#[cfg(feature = "std")]
+const MIN_SLEEP: std::time::Duration = std::time::Duration::from_micros(50);
+#[cfg(feature = "std")]
+const MAX_SLEEP: std::time::Duration = std::time::Duration::from_millis(1);
+
+const fn encode(rounds: Option<u32>) -> u32 {
+    match rounds {
+        Some(rounds) => rounds,
+        None => u32::MAX,
+    }
+}
+
+/// Process-wide tuning of how `Backoff` waits, so e.g. a noisy VM where lock
+/// holders get descheduled can give up the CPU sooner, without rebuilding.
+/// Set with `configure`; counts are of failed attempts within one wait.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BackoffConfig {
+    /// The most `spin_loop()` hints in one round, the backoff doubles up to
+    /// this. At least 1.
+    pub max_spins: u32,
+    /// Yield to the OS scheduler instead of spinning after this many rounds.
+    /// Only with `std`.
+    pub yield_after: Option<u32>,
+    /// Sleep instead after this many rounds, 50µs at first and twice as long
+    /// each round after, up to 1ms. Only with `std`.
+    pub sleep_after: Option<u32>,
+}
+
+impl BackoffConfig {
+    /// Up to 64 hints a round, then yields after 100 rounds and sleeps after
+    /// 200, so a waiter whose lock holder got descheduled (say, on an
+    /// oversubscribed CI machine) lets it run instead of burning its time
+    /// slice. Without `std` there's nothing to escalate to, it only spins.
+    #[cfg(feature = "std")]
+    pub const DEFAULT: Self = Self {
+        max_spins: 64,
+        yield_after: Some(100),
+        sleep_after: Some(200),
+    };
+
+    /// Spins only, up to 64 hints a round.
+    #[cfg(not(feature = "std"))]
+    pub const DEFAULT: Self = Self {
+        max_spins: 64,
+        yield_after: None,
+        sleep_after: None,
+    };
+
+    /// The configuration in effect.
+    pub fn current() -> Self {
+        let get = |value: &AtomicU32| Some(value.load(Relaxed)).filter(|&n| n != u32::MAX);
+        Self {
+            max_spins: MAX_SPINS.load(Relaxed),
+            yield_after: get(&YIELD_AFTER),
+            sleep_after: get(&SLEEP_AFTER),
+        }
+    }
+}
+
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Changes how every `Backoff` waits from now on. Meant to be called once at
+/// startup; waits already in progress may see the change partway through.
+///
+/// All of it applies to the spinning primitives: `SpinLock` and the locks
+/// built on it with the default `Relax`, `TicketLock`, `McsLock`, `ClhLock`,
+/// `CohortLock`, `RwSpinLock`, `PhaseFairRwLock`, `BravoRwLock`, `SeqLock`,
+/// `ReentrantSpinLock`, `SpinSemaphore`, `SpinBarrier`, `SpinQueue`,
+/// `SpinOnce`, `KeyedLock`, and `SpinCondvar` and the `spsc` channel with
+/// the default `Relax`. `SpinLock`s with another `Relax` wait their own way.
+///
+/// Only `max_spins` applies to `Event`, `Latch`, `WaitGroup`, `PriorityLock`
+/// and `RcuCell` writers, which yield as soon as the backoff is at its
+/// limit, and to `HybridMutex`, which parks the thread instead. `FutexLock`
+/// and the async locks don't use `Backoff` at all.
+pub fn configure(config: BackoffConfig) {
+    MAX_SPINS.store(config.max_spins.max(1), Relaxed);
+    YIELD_AFTER.store(encode(config.yield_after), Relaxed);
+    SLEEP_AFTER.store(encode(config.sleep_after), Relaxed);
+}
+
+/// Exponential backoff for spin loops, with optional randomized jitter.
+pub struct Backoff {
+    step: u32,
+    // xorshift state, zero means jitter is disabled
+    seed: u32,
+    // relax() calls so far, for `BackoffConfig::yield_after` and `sleep_after`
+    rounds: u32,
+}
+
+impl Backoff {
+    pub const fn new() -> Self {
+        Self {
+            step: 0,
+            seed: 0,
+            rounds: 0,
+        }
+    }
+
+    /// Like `new`, but each wait is a random length between half and all of
+    /// the current step, so threads that collided once don't keep colliding.
+    pub fn with_jitter() -> Self {
+        Self {
+            step: 0,
+            seed: SEED.fetch_add(0x9e37_79b9, Relaxed) | 1,
+            rounds: 0,
+        }
+    }
+
+    pub fn spin(&mut self) {
+        let max = MAX_SPINS.load(Relaxed);
+        let full = (1u32 << self.step).min(max);
+        let mut spins = full;
+        if self.seed != 0 {
+            self.seed ^= self.seed << 13;
+            self.seed ^= self.seed >> 17;
+            self.seed ^= self.seed << 5;
+            spins = spins / 2 + self.seed % (spins / 2 + 1);
+        }
+        // under loom every hint is one more interleaving to explore
+        #[cfg(loom)]
+        let spins = spins.min(1);
+        for _ in 0..spins {
+            spin_loop();
+        }
+        if full < max && self.step < 31 {
+            self.step += 1;
+        }
+    }
+
+    // Whether spin() has grown as far as it goes.
+    #[cfg(feature = "std")]
+    fn at_limit(&self) -> bool {
+        1u32 << self.step >= MAX_SPINS.load(Relaxed)
+    }
+
+    /// `spin` for waits that may go on a while: once the backoff has grown
+    /// to its limit, yields to the OS scheduler instead (with `std`; without
+    /// it, keeps spinning), so the thread being waited on gets to run.
+    pub fn snooze(&mut self) {
+        #[cfg(feature = "std")]
+        if self.at_limit() {
+            crate::sync::yield_now();
+            return;
+        }
+        self.spin();
+    }
+
+    /// One round of waiting as the default `Relax`: `spin`, then yield and
+    /// finally sleep once past the configured `yield_after` and
+    /// `sleep_after` rounds, see `BackoffConfig`.
+    #[inline]
+    pub fn relax(&mut self) {
+        if !self.escalate() {
+            self.spin();
+        }
+    }
+
+    // Counts a round, and yields or sleeps in place of spinning once past
+    // `yield_after` or `sleep_after`. False means the caller still spins,
+    // for waits that spin their own way (TicketLock's spins by its place in
+    // line).
+    #[inline]
+    pub(crate) fn escalate(&mut self) -> bool {
+        self.rounds = self.rounds.saturating_add(1);
+        #[cfg(feature = "std")]
+        {
+            let sleep_after = SLEEP_AFTER.load(Relaxed);
+            if self.rounds > sleep_after {
+                let doublings = (self.rounds - sleep_after - 1).min(5);
+                crate::sync::sleep((MIN_SLEEP * (1 << doublings)).min(MAX_SLEEP));
+                return true;
+            }
+            if self.rounds > YIELD_AFTER.load(Relaxed) {
+                crate::sync::yield_now();
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn reset(&mut self) {
+        self.step = 0;
+        self.rounds = 0;
+    }
+}
+
+// Jittered, since that's what every lock in the crate wants by default.
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::with_jitter()
+    }
+}
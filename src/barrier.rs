@@ -0,0 +1,47 @@
+use core::sync::atomic::{AtomicBool, AtomicUsize};
+
+use crate::sync::{AcqRel, Acquire, Relaxed, Release};
+use crate::Backoff;
+
+/// Rendezvous point for a fixed number of threads. Reusable: once all `n`
+/// have arrived they are released together and the barrier is ready for the
+/// next round.
+pub struct SpinBarrier {
+    n: usize,
+    // threads still to arrive this round
+    count: AtomicUsize,
+    // flipped by the last thread of each round; waiters spin until it does
+    sense: AtomicBool,
+}
+
+impl SpinBarrier {
+    /// Like std's `Barrier`, `n == 0` behaves the same as `n == 1`.
+    pub const fn new(n: usize) -> Self {
+        let n = if n == 0 { 1 } else { n };
+        Self {
+            n,
+            count: AtomicUsize::new(n),
+            sense: AtomicBool::new(false),
+        }
+    }
+
+    /// Blocks until all `n` threads have called `wait`. Exactly one of them,
+    /// the last to arrive, gets `true` back.
+    pub fn wait(&self) -> bool {
+        // can't flip before we've arrived, so this is the current round's sense
+        let sense = !self.sense.load(Relaxed);
+        if self.count.fetch_sub(1, AcqRel) == 1 {
+            // reset before releasing anyone, so early leavers count into the
+            // next round
+            self.count.store(self.n, Relaxed);
+            self.sense.store(sense, Release);
+            return true;
+        }
+
+        let mut backoff = Backoff::with_jitter();
+        while self.sense.load(Acquire) != sense {
+            backoff.relax();
+        }
+        false
+    }
+}
@@ -0,0 +1,203 @@
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering::SeqCst};
+
+use crate::sync::{Relaxed, Release};
+use crate::{Backoff, RawRwSpinLock};
+
+// Slots in the visible readers table, shared by every BravoRwLock. A fast
+// path reader puts its lock's address in the slot its thread and lock hash
+// to, so readers of the same lock on different threads write to different
+// cache lines (mostly, neighbouring slots share one).
+const SLOTS: usize = 4096;
+
+// Slow path reads after a writer revoked the bias before readers may set it
+// again, so a write-heavy phase doesn't pay for a table scan on every write.
+const INHIBIT_READS: u32 = 64;
+
+static VISIBLE: [AtomicUsize; SLOTS] = [const { AtomicUsize::new(0) }; SLOTS];
+
+/// Reader-writer lock for read-mostly data on many cores (Dice and Kogan's
+/// BRAVO). While reads dominate, a reader doesn't touch the lock at all: it
+/// only marks itself in a table slot picked by its thread, so readers on
+/// different cores don't bounce a shared counter's cache line between them.
+/// A writer turns that off and waits for the marked readers to leave, then
+/// readers go through a `RwSpinLock` until enough of them shows reads are
+/// back to dominating.
+///
+/// Writes get a lot more expensive while the bias is on (a scan of the whole
+/// table), so for data that's written about as often as it's read, a plain
+/// `RwSpinLock` does better.
+///
+/// ```
+/// let config = spinlock::BravoRwLock::new(1);
+/// std::thread::scope(|s| {
+///     for _ in 0..4 {
+///         s.spawn(|| assert!(*config.read() >= 1));
+///     }
+///     *config.write() += 1;
+/// });
+/// assert_eq!(*config.read(), 2);
+/// ```
+pub struct BravoRwLock<T> {
+    // readers may take the fast path
+    biased: AtomicBool,
+    // slow path reads left until the bias may come back
+    inhibit: AtomicU32,
+    raw: RawRwSpinLock,
+    value: UnsafeCell<T>,
+}
+
+// Same bound as RwSpinLock's: readers share &T across threads.
+unsafe impl<T> Sync for BravoRwLock<T> where T: Send + Sync {}
+
+impl<T> BravoRwLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            biased: AtomicBool::new(true),
+            inhibit: AtomicU32::new(0),
+            raw: RawRwSpinLock::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline]
+    pub fn read(&self) -> BravoReadGuard<'_, T> {
+        if self.biased.load(Relaxed) {
+            let slot = &VISIBLE[self.slot()];
+            let addr = self as *const Self as usize;
+            if slot.compare_exchange(0, addr, SeqCst, Relaxed).is_ok() {
+                // A writer clears the bias before scanning the table, so
+                // either it sees our slot or we see the bias gone.
+                if self.biased.load(SeqCst) {
+                    return BravoReadGuard {
+                        lock: self,
+                        slot: Some(slot),
+                    };
+                }
+                slot.store(0, Release);
+            }
+        }
+        self.read_slow()
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn read_slow(&self) -> BravoReadGuard<'_, T> {
+        self.raw.read();
+        // Holding a read lock keeps writers out, so none can be revoking the
+        // bias while it's turned back on. Released, since fast path readers
+        // don't go through `raw` to see the last writer's changes.
+        if !self.biased.load(Relaxed)
+            && self
+                .inhibit
+                .fetch_update(Relaxed, Relaxed, |left| left.checked_sub(1))
+                .is_err()
+        {
+            self.biased.store(true, Release);
+        }
+        BravoReadGuard {
+            lock: self,
+            slot: None,
+        }
+    }
+
+    #[inline]
+    pub fn write(&self) -> BravoWriteGuard<'_, T> {
+        self.raw.write();
+        if self.biased.load(Relaxed) {
+            self.revoke();
+        }
+        BravoWriteGuard { lock: self }
+    }
+
+    // Turns the fast path off and waits for the readers that took it.
+    #[cold]
+    #[inline(never)]
+    fn revoke(&self) {
+        self.biased.store(false, SeqCst);
+        let addr = self as *const Self as usize;
+        let mut backoff = Backoff::with_jitter();
+        for slot in &VISIBLE {
+            while slot.load(SeqCst) == addr {
+                backoff.relax();
+            }
+        }
+        self.inhibit.store(INHIBIT_READS, Relaxed);
+    }
+
+    // The calling thread's slot for this lock.
+    #[inline]
+    fn slot(&self) -> usize {
+        let addr = self as *const Self as u64;
+        let hash =
+            (crate::thread_id::current() as u64 ^ (addr >> 4)).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        (hash >> 52) as usize % SLOTS
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T: Default> Default for BravoRwLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+pub struct BravoReadGuard<'a, T> {
+    lock: &'a BravoRwLock<T>,
+    // the table slot for a fast path read, None for one through `raw`
+    slot: Option<&'static AtomicUsize>,
+}
+
+impl<T> Deref for BravoReadGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: writers wait for every reader, in the table or in `raw`
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for BravoReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        match self.slot {
+            Some(slot) => slot.store(0, Release),
+            // safety: the guard is the proof we hold a read lock
+            None => unsafe { self.lock.raw.unlock_read() },
+        }
+    }
+}
+
+pub struct BravoWriteGuard<'a, T> {
+    lock: &'a BravoRwLock<T>,
+}
+
+impl<T> Deref for BravoWriteGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: the writer holds `raw` and waited out the fast path readers
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for BravoWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: the writer holds `raw` and waited out the fast path readers
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for BravoWriteGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // safety: the guard is the proof we hold the write lock
+        unsafe { self.lock.raw.unlock_write() }
+    }
+}
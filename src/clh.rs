@@ -0,0 +1,120 @@
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+
+use crate::sync::{AcqRel, Acquire, AtomicBool, AtomicPtr, Relaxed, Release};
+use crate::Backoff;
+
+pub(crate) struct ClhNode {
+    locked: AtomicBool,
+}
+
+/// CLH queue lock: the queue is implicit, each waiter only knows its
+/// predecessor's node and spins on that. Nodes are allocated per acquisition
+/// and freed by whoever takes the lock after them, so callers never have to
+/// provide one (unlike `McsLock`).
+pub struct ClhLock<T> {
+    // null while nobody holds or waits for the lock
+    pub(crate) tail: AtomicPtr<ClhNode>,
+    pub(crate) value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for ClhLock<T> where T: Send {}
+
+impl<T> ClhLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            tail: AtomicPtr::new(ptr::null_mut()),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline]
+    pub fn lock(&self) -> ClhGuard<'_, T> {
+        let node = Box::into_raw(Box::new(ClhNode {
+            locked: AtomicBool::new(true),
+        }));
+        let pred = self.tail.swap(node, AcqRel);
+        if !pred.is_null() {
+            self.lock_slow(pred);
+        }
+        ClhGuard { lock: self, node }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn lock_slow(&self, pred: *mut ClhNode) {
+        // safety: the predecessor's node is only freed by its successor, us
+        let mut backoff = Backoff::new();
+        while unsafe { (*pred).locked.load(Acquire) } {
+            backoff.relax();
+        }
+        // safety: its owner is done with it, and nobody else can reach it anymore
+        drop(unsafe { Box::from_raw(pred) });
+    }
+
+    #[inline]
+    pub fn try_lock(&self) -> Option<ClhGuard<'_, T>> {
+        if !self.tail.load(Relaxed).is_null() {
+            return None;
+        }
+        let node = Box::into_raw(Box::new(ClhNode {
+            locked: AtomicBool::new(true),
+        }));
+        match self
+            .tail
+            .compare_exchange(ptr::null_mut(), node, Acquire, Relaxed)
+        {
+            Ok(_) => Some(ClhGuard { lock: self, node }),
+            Err(_) => {
+                // safety: never published
+                drop(unsafe { Box::from_raw(node) });
+                None
+            }
+        }
+    }
+}
+
+pub struct ClhGuard<'a, T> {
+    pub(crate) lock: &'a ClhLock<T>,
+    node: *mut ClhNode,
+}
+
+// Like `Guard`: the node is on the heap and only touched through atomics,
+// so whichever thread ends up with the guard can release it.
+unsafe impl<T: Send> Send for ClhGuard<'_, T> {}
+unsafe impl<T: Sync> Sync for ClhGuard<'_, T> {}
+
+impl<T> Deref for ClhGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: only the thread whose predecessor released has a guard
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for ClhGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: only the thread whose predecessor released has a guard
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for ClhGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        if self
+            .lock
+            .tail
+            .compare_exchange(self.node, ptr::null_mut(), Release, Relaxed)
+            .is_ok()
+        {
+            // nobody queued behind us, so the node is still ours to free
+            drop(unsafe { Box::from_raw(self.node) });
+        } else {
+            // the successor spins on this flag and frees the node afterwards
+            unsafe { (*self.node).locked.store(false, Release) };
+        }
+    }
+}
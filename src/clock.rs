@@ -0,0 +1,12 @@
+use std::sync::OnceLock;
+use std::time::Instant;
+
+// Times are kept as nanoseconds since this, so they fit in an atomic.
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+pub(crate) fn now() -> u64 {
+    epoch().elapsed().as_nanos() as u64
+}
@@ -0,0 +1,235 @@
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+use crate::sync::{Acquire, AtomicBool, AtomicUsize, Relaxed, Release};
+use crate::{Backoff, CachePadded};
+
+// Nodes beyond this share cohorts (node ids are taken modulo MAX_NODES).
+const MAX_NODES: usize = 8;
+// Local handoffs in a row before the global lock goes back up for grabs, so
+// other nodes can't be starved by a busy one.
+const MAX_BATCH: usize = 64;
+
+// One per NUMA node.
+struct Cohort {
+    next_ticket: AtomicUsize,
+    now_serving: AtomicUsize,
+    // set by a releaser that handed the global lock over along with the local one
+    global_passed: AtomicBool,
+    // only touched by whoever holds this cohort's local lock
+    batch: AtomicUsize,
+}
+
+impl Cohort {
+    const fn new() -> Self {
+        Self {
+            next_ticket: AtomicUsize::new(0),
+            now_serving: AtomicUsize::new(0),
+            global_passed: AtomicBool::new(false),
+            batch: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// NUMA-aware cohort lock (ticket lock of ticket locks). Threads first queue
+/// on a lock local to their NUMA node; a releaser with a local waiter hands
+/// over the global lock directly instead of releasing it, keeping the data
+/// on one socket for up to `MAX_BATCH` acquisitions at a time.
+///
+/// With the `numa` feature the current node is detected from the CPU the
+/// thread runs on, otherwise `lock()` treats everything as node 0 and
+/// callers who know their node can use `lock_on`.
+pub struct CohortLock<T> {
+    global_next: AtomicUsize,
+    global_serving: AtomicUsize,
+    // padded so cohorts on different nodes don't share lines
+    cohorts: [CachePadded<Cohort>; MAX_NODES],
+    pub(crate) value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for CohortLock<T> where T: Send {}
+
+impl<T> CohortLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            global_next: AtomicUsize::new(0),
+            global_serving: AtomicUsize::new(0),
+            cohorts: [const { CachePadded::new(Cohort::new()) }; MAX_NODES],
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline]
+    pub fn lock(&self) -> CohortGuard<'_, T> {
+        self.lock_on(topology::current_node())
+    }
+
+    #[inline]
+    pub fn lock_on(&self, node: usize) -> CohortGuard<'_, T> {
+        let node = node % MAX_NODES;
+        let cohort = &self.cohorts[node];
+        let ticket = cohort.next_ticket.fetch_add(1, Relaxed);
+        if cohort.now_serving.load(Acquire) != ticket {
+            wait_for(&cohort.now_serving, ticket);
+        }
+        if !cohort.global_passed.load(Relaxed) {
+            let ticket = self.global_next.fetch_add(1, Relaxed);
+            if self.global_serving.load(Acquire) != ticket {
+                wait_for(&self.global_serving, ticket);
+            }
+        }
+        CohortGuard {
+            lock: self,
+            node,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn try_lock(&self) -> Option<CohortGuard<'_, T>> {
+        let node = topology::current_node() % MAX_NODES;
+        let cohort = &self.cohorts[node];
+        let local = cohort.now_serving.load(Acquire);
+        cohort
+            .next_ticket
+            .compare_exchange(local, local.wrapping_add(1), Acquire, Relaxed)
+            .ok()?;
+        if !cohort.global_passed.load(Acquire) {
+            let global = self.global_serving.load(Acquire);
+            if self
+                .global_next
+                .compare_exchange(global, global.wrapping_add(1), Acquire, Relaxed)
+                .is_err()
+            {
+                cohort.now_serving.store(local.wrapping_add(1), Release);
+                return None;
+            }
+        }
+        Some(CohortGuard {
+            lock: self,
+            node,
+            marker: PhantomData,
+        })
+    }
+}
+
+// Both tickets are waited out the same way, the slow path of `lock_on`.
+#[cold]
+#[inline(never)]
+fn wait_for(serving: &AtomicUsize, ticket: usize) {
+    let mut backoff = Backoff::new();
+    while serving.load(Acquire) != ticket {
+        backoff.relax();
+    }
+}
+
+pub struct CohortGuard<'a, T> {
+    pub(crate) lock: &'a CohortLock<T>,
+    node: usize,
+    // Send and Sync are implemented below rather than following `lock`
+    marker: PhantomData<*const ()>,
+}
+
+// Releasing from another node's thread is just slower to hand over.
+unsafe impl<T: Send> Send for CohortGuard<'_, T> {}
+unsafe impl<T: Sync> Sync for CohortGuard<'_, T> {}
+
+impl<T> Deref for CohortGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: the guard holds both its local lock and the global one
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for CohortGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: the guard holds both its local lock and the global one
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for CohortGuard<'_, T> {
+    fn drop(&mut self) {
+        let cohort = &self.lock.cohorts[self.node];
+        let next = cohort.now_serving.load(Relaxed).wrapping_add(1);
+        let local_waiter = cohort.next_ticket.load(Relaxed) != next;
+        let batch = cohort.batch.load(Relaxed);
+        if local_waiter && batch < MAX_BATCH {
+            cohort.batch.store(batch + 1, Relaxed);
+            cohort.global_passed.store(true, Relaxed);
+        } else {
+            cohort.batch.store(0, Relaxed);
+            cohort.global_passed.store(false, Relaxed);
+            let serving = self.lock.global_serving.load(Relaxed);
+            self.lock
+                .global_serving
+                .store(serving.wrapping_add(1), Release);
+        }
+        // publishes global_passed to the next local holder along with the data
+        cohort.now_serving.store(next, Release);
+    }
+}
+
+#[cfg(all(feature = "numa", target_os = "linux"))]
+mod topology {
+    use std::fs;
+    use std::sync::OnceLock;
+
+    // cpu index -> node index, read once from sysfs
+    static CPU_NODES: OnceLock<Vec<usize>> = OnceLock::new();
+
+    pub(crate) fn current_node() -> usize {
+        let map = CPU_NODES.get_or_init(read_cpu_nodes);
+        // safety: no preconditions, returns -1 on failure
+        let cpu = unsafe { libc::sched_getcpu() };
+        usize::try_from(cpu)
+            .ok()
+            .and_then(|cpu| map.get(cpu).copied())
+            .unwrap_or(0)
+    }
+
+    fn read_cpu_nodes() -> Vec<usize> {
+        let mut map = Vec::new();
+        let Ok(entries) = fs::read_dir("/sys/devices/system/node") else {
+            return map;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let Some(node) = name
+                .to_str()
+                .and_then(|n| n.strip_prefix("node"))
+                .and_then(|n| n.parse::<usize>().ok())
+            else {
+                continue;
+            };
+            let Ok(list) = fs::read_to_string(entry.path().join("cpulist")) else {
+                continue;
+            };
+            for cpu in parse_cpu_list(list.trim()) {
+                if map.len() <= cpu {
+                    map.resize(cpu + 1, 0);
+                }
+                map[cpu] = node;
+            }
+        }
+        map
+    }
+
+    // "0-3,8,10-11" -> 0, 1, 2, 3, 8, 10, 11
+    fn parse_cpu_list(list: &str) -> impl Iterator<Item = usize> + '_ {
+        list.split(',').filter(|r| !r.is_empty()).flat_map(|range| {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let start = start.parse().unwrap_or(0);
+            let end = end.parse().unwrap_or(start);
+            start..=end
+        })
+    }
+}
+
+#[cfg(not(all(feature = "numa", target_os = "linux")))]
+mod topology {
+    pub(crate) fn current_node() -> usize {
+        0
+    }
+}
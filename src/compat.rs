@@ -0,0 +1,8 @@
+//! Drop-in replacement for `std::sync::Mutex`. The types and signatures
+//! match std's, so switching a codebase over is a matter of changing
+//! `use std::sync::{Mutex, MutexGuard}` to `use spinlock::compat::{...}`.
+
+pub use std::sync::{LockResult, PoisonError, TryLockError, TryLockResult};
+
+pub type Mutex<T> = crate::PoisonSpinLock<T>;
+pub type MutexGuard<'a, T> = crate::PoisonGuard<'a, T>;
@@ -0,0 +1,86 @@
+use crate::sync::{Acquire, AtomicU32, AtomicUsize, Relaxed, Release};
+use crate::{Guard, Relax};
+
+/// Condition variable for `SpinLock`. Waiting threads release the lock and
+/// spin (with the lock's own `Relax` strategy) until notified, then take the
+/// lock again. Like any condvar it can wake spuriously, so wait in a loop
+/// over the actual condition, or use `wait_while`.
+pub struct SpinCondvar {
+    // bumped by notify_all, every waiter that saw the old value wakes up
+    generation: AtomicU32,
+    // threads currently inside wait()
+    waiters: AtomicUsize,
+    // notify_one wakeups not yet taken by a waiter, never more than `waiters`
+    signals: AtomicUsize,
+}
+
+impl SpinCondvar {
+    pub const fn new() -> Self {
+        Self {
+            generation: AtomicU32::new(0),
+            waiters: AtomicUsize::new(0),
+            signals: AtomicUsize::new(0),
+        }
+    }
+
+    /// Unlocks the guard's lock, waits for a notification and locks it again.
+    pub fn wait<'a, T: ?Sized, R: Relax>(&self, guard: Guard<'a, T, R>) -> Guard<'a, T, R> {
+        let lock = guard.lock;
+        // Registered before unlocking, so anyone who changes the condition
+        // under the lock afterwards is bound to see us.
+        let generation = self.generation.load(Relaxed);
+        self.waiters.fetch_add(1, Relaxed);
+        drop(guard);
+
+        let mut relax = R::default();
+        while self.generation.load(Relaxed) == generation && !self.take_signal() {
+            relax.relax();
+        }
+
+        // after taking the signal, see notify_one
+        self.waiters.fetch_sub(1, Release);
+        lock.lock()
+    }
+
+    /// Waits until `condition` returns false, re-checking it on every wakeup.
+    pub fn wait_while<'a, T: ?Sized, R: Relax, F>(
+        &self,
+        mut guard: Guard<'a, T, R>,
+        mut condition: F,
+    ) -> Guard<'a, T, R>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            guard = self.wait(guard);
+        }
+        guard
+    }
+
+    fn take_signal(&self) -> bool {
+        self.signals
+            .fetch_update(Relaxed, Relaxed, |s| s.checked_sub(1))
+            .is_ok()
+    }
+
+    pub fn notify_one(&self) {
+        // Waiters take their signal before leaving, so reading the count
+        // first means any waiter missing from it has its signal gone from
+        // `signals` too. The other way round, a waiter leaving in between
+        // makes its taken signal look pending and this one get dropped.
+        let waiters = self.waiters.load(Acquire);
+        let _ = self
+            .signals
+            .fetch_update(Relaxed, Relaxed, |s| (s < waiters).then_some(s + 1));
+    }
+
+    pub fn notify_all(&self) {
+        self.generation.fetch_add(1, Relaxed);
+    }
+}
+
+impl Default for SpinCondvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,206 @@
+//! Deadlock detection for the crate's spinlocks, like `parking_lot`'s.
+//!
+//! With the `deadlock_detection` feature every `RawSpinLock` (and so
+//! `SpinLock` and the locks built on it: `PoisonSpinLock`, `OrderedSpinLock`,
+//! `HierarchicalSpinLock`, `IrqSpinLock`) and every `RwSpinLock` records who
+//! holds it and who has been waiting on it for a while, timed and cancellable
+//! waits included, in one global waits-for graph. `check_deadlock` looks for
+//! cycles in that graph, e.g. from a watchdog thread:
+//!
+//! ```no_run
+//! # #[cfg(feature = "deadlock_detection")]
+//! std::thread::spawn(|| loop {
+//!     std::thread::sleep(std::time::Duration::from_secs(10));
+//!     for (i, cycle) in spinlock::deadlock::check_deadlock().iter().enumerate() {
+//!         eprintln!("deadlock #{i}");
+//!         for thread in cycle {
+//!             eprintln!("{:?}\n{}", thread.thread_id(), thread.backtrace());
+//!         }
+//!     }
+//! });
+//! ```
+//!
+//! Every lock and unlock goes through a global mutex, so this is for
+//! debugging, not production. Waiters are only added to the graph after
+//! spinning for a while, since capturing their backtrace is slow.
+//!
+//! The other locks aren't in the graph: `TicketLock`, `McsLock`, `ClhLock`,
+//! `CohortLock`, `PhaseFairRwLock`, `BravoRwLock`, `ReentrantSpinLock`,
+//! `HybridMutex`, `FutexLock` and the async locks. A cycle that goes through
+//! one of them isn't reported.
+
+use std::backtrace::Backtrace;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
+
+use crate::thread_id;
+
+// Relaxes before a waiter is considered stuck and added to the graph.
+const WAIT_SPINS: u32 = 1 << 12;
+
+struct Waiting {
+    lock: usize,
+    thread_id: ThreadId,
+    backtrace: Arc<Backtrace>,
+}
+
+#[derive(Default)]
+struct Graph {
+    // lock address -> threads holding it, several for read locks
+    holders: HashMap<usize, Vec<usize>>,
+    // thread -> what it's stuck on
+    waiting: HashMap<usize, Waiting>,
+}
+
+static GRAPH: Mutex<Option<Graph>> = Mutex::new(None);
+
+// Never poisons: nothing in here panics while holding the graph.
+fn graph() -> MutexGuard<'static, Option<Graph>> {
+    GRAPH.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// One thread taking part in a deadlock.
+pub struct DeadlockedThread {
+    thread_id: ThreadId,
+    backtrace: Arc<Backtrace>,
+}
+
+impl DeadlockedThread {
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// Where the thread started waiting.
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.backtrace
+    }
+}
+
+/// Every cycle of threads waiting on locks held by each other right now. A
+/// deadlock that persists is reported again on every call.
+pub fn check_deadlock() -> Vec<Vec<DeadlockedThread>> {
+    let guard = graph();
+    let Some(graph) = guard.as_ref() else {
+        return Vec::new();
+    };
+
+    let mut cycles: Vec<Vec<usize>> = Vec::new();
+    for &start in graph.waiting.keys() {
+        find_cycles(graph, start, start, &mut Vec::new(), &mut cycles);
+    }
+
+    cycles
+        .into_iter()
+        .map(|cycle| {
+            cycle
+                .into_iter()
+                .map(|thread| {
+                    let waiting = &graph.waiting[&thread];
+                    DeadlockedThread {
+                        thread_id: waiting.thread_id,
+                        backtrace: waiting.backtrace.clone(),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Depth-first through thread -> lock it waits on -> threads holding that
+// lock, only visiting threads numbered above `start` so each cycle is found
+// once, from its smallest thread.
+fn find_cycles(
+    graph: &Graph,
+    start: usize,
+    thread: usize,
+    path: &mut Vec<usize>,
+    cycles: &mut Vec<Vec<usize>>,
+) {
+    let Some(waiting) = graph.waiting.get(&thread) else {
+        return;
+    };
+    let Some(holders) = graph.holders.get(&waiting.lock) else {
+        return;
+    };
+    path.push(thread);
+    for &holder in holders {
+        if holder == start {
+            cycles.push(path.clone());
+        } else if holder > start && !path.contains(&holder) {
+            find_cycles(graph, start, holder, path, cycles);
+        }
+    }
+    path.pop();
+}
+
+pub(crate) fn acquired(lock: usize) {
+    let me = thread_id::current();
+    graph()
+        .get_or_insert_with(Graph::default)
+        .holders
+        .entry(lock)
+        .or_default()
+        .push(me);
+}
+
+// Guards may be dropped on another thread than the one that locked, in which
+// case any one holder goes; for exclusive locks there's only one anyway.
+pub(crate) fn released(lock: usize) {
+    let me = thread_id::current();
+    let mut guard = graph();
+    let Some(graph) = guard.as_mut() else {
+        return;
+    };
+    if let Some(holders) = graph.holders.get_mut(&lock) {
+        let i = holders.iter().position(|&t| t == me).unwrap_or(0);
+        if i < holders.len() {
+            holders.swap_remove(i);
+        }
+        if holders.is_empty() {
+            graph.holders.remove(&lock);
+        }
+    }
+}
+
+/// Tracks one thread's wait for a lock, entering it into the graph once it
+/// has spun for long enough and taking it out again on drop.
+pub(crate) struct Wait {
+    lock: usize,
+    spins: u32,
+}
+
+impl Wait {
+    pub(crate) fn new<L>(lock: &L) -> Self {
+        Self {
+            lock: lock as *const L as usize,
+            spins: 0,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn tick(&mut self) {
+        self.spins = self.spins.saturating_add(1);
+        if self.spins == WAIT_SPINS {
+            let waiting = Waiting {
+                lock: self.lock,
+                thread_id: thread::current().id(),
+                backtrace: Arc::new(Backtrace::force_capture()),
+            };
+            graph()
+                .get_or_insert_with(Graph::default)
+                .waiting
+                .insert(thread_id::current(), waiting);
+        }
+    }
+}
+
+impl Drop for Wait {
+    fn drop(&mut self) {
+        if self.spins >= WAIT_SPINS {
+            if let Some(graph) = graph().as_mut() {
+                graph.waiting.remove(&thread_id::current());
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Who holds a lock, with the `diagnostics` feature, for debugging a wedged
+//! spinlock without guesswork.
+//!
+//! Every `RawSpinLock` (and so `SpinLock` and the locks built on it) records
+//! the thread that took it, when, and a backtrace of where. `lock_timeout`
+//! puts all of that in its error:
+//!
+//! ```
+//! # #[cfg(feature = "diagnostics")] {
+//! use std::time::Duration;
+//!
+//! let lock = spinlock::SpinLock::new_named("config", 0);
+//! let _held = lock.lock();
+//! let held_by = std::thread::current().id();
+//! std::thread::scope(|s| {
+//!     s.spawn(|| {
+//!         let Err(err) = lock.lock_timeout(Duration::from_millis(10)) else {
+//!             unreachable!("the main thread holds it");
+//!         };
+//!         // timed out after 10ms waiting for spinlock "config", held by
+//!         // thread "main" for 10ms, acquired at: ...
+//!         eprintln!("{err}");
+//!         assert_eq!(err.owner().unwrap().thread().id(), held_by);
+//!     });
+//! });
+//! # }
+//! ```
+//!
+//! This makes every lock and unlock a good deal slower, so it's meant for
+//! debug builds. Backtraces are only captured when enabled through
+//! `RUST_LIB_BACKTRACE` or `RUST_BACKTRACE`, see `Backtrace::capture`; the
+//! thread and time always are.
+
+use core::fmt;
+use core::time::Duration;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+/// The thread holding a lock, and since when.
+#[derive(Clone, Debug)]
+pub struct Owner {
+    thread: Thread,
+    since: Instant,
+    backtrace: Arc<Backtrace>,
+}
+
+impl Owner {
+    pub fn thread(&self) -> &Thread {
+        &self.thread
+    }
+
+    /// When the lock was taken.
+    pub fn since(&self) -> Instant {
+        self.since
+    }
+
+    /// Where the lock was taken, if backtraces are enabled.
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.backtrace
+    }
+}
+
+/// The error of `lock_timeout`: the lock was still held at the deadline.
+#[derive(Debug)]
+pub struct TimedOut {
+    name: Option<&'static str>,
+    // as an integer, so the error is Send and Sync
+    addr: usize,
+    waited: Duration,
+    owner: Option<Owner>,
+}
+
+impl TimedOut {
+    /// The name from `SpinLock::new_named`, if it had one.
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    pub fn addr(&self) -> *const () {
+        self.addr as *const ()
+    }
+
+    pub fn waited(&self) -> Duration {
+        self.waited
+    }
+
+    /// Who held the lock when the wait gave up. None if it was unlocked
+    /// just then, only to be taken again before the waiter got to it.
+    pub fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+}
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timed out after {:?} waiting for ", self.waited)?;
+        match self.name {
+            Some(name) => write!(f, "spinlock {name:?}")?,
+            None => write!(f, "spinlock at {:?}", self.addr())?,
+        }
+        let Some(owner) = &self.owner else {
+            return Ok(());
+        };
+        match owner.thread.name() {
+            Some(name) => write!(f, ", held by thread {name:?}")?,
+            None => write!(f, ", held by thread {:?}", owner.thread.id())?,
+        }
+        write!(f, " for {:?}", owner.since.elapsed())?;
+        match owner.backtrace.status() {
+            BacktraceStatus::Captured => write!(f, ", acquired at:\n{}", owner.backtrace),
+            _ => write!(
+                f,
+                " (set RUST_LIB_BACKTRACE=1 to see where it was acquired)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+// The owner of one lock, set by whoever takes it
+pub(crate) struct Slot(Mutex<Option<Owner>>);
+
+impl Slot {
+    pub(crate) const fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    pub(crate) fn acquired(&self) {
+        let owner = Owner {
+            thread: thread::current(),
+            since: Instant::now(),
+            backtrace: Arc::new(Backtrace::capture()),
+        };
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(owner);
+    }
+
+    pub(crate) fn released(&self) {
+        let owner = self.0.lock().unwrap_or_else(|e| e.into_inner()).take();
+        // dropped outside the mutex, a captured backtrace is big
+        drop(owner);
+    }
+
+    pub(crate) fn owner(&self) -> Option<Owner> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub(crate) fn timed_out(
+        &self,
+        name: Option<&'static str>,
+        addr: *const (),
+        waited: Duration,
+    ) -> TimedOut {
+        TimedOut {
+            name,
+            addr: addr as usize,
+            waited,
+            owner: self.owner(),
+        }
+    }
+}
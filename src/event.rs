@@ -0,0 +1,64 @@
+use core::sync::atomic::AtomicBool;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+use crate::sync::{Acquire, Relaxed, Release};
+use crate::Backoff;
+
+/// Manual-reset flag for signalling things like "initialization complete" or
+/// "shutdown requested": once `set`, every `wait` returns straight away until
+/// someone `reset`s it.
+pub struct Event {
+    set: AtomicBool,
+}
+
+impl Event {
+    pub const fn new() -> Self {
+        Self {
+            set: AtomicBool::new(false),
+        }
+    }
+
+    /// Releases all current and future waiters. Everything done before is
+    /// visible to them.
+    pub fn set(&self) {
+        self.set.store(true, Release);
+    }
+
+    pub fn reset(&self) {
+        self.set.store(false, Relaxed);
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.set.load(Acquire)
+    }
+
+    /// Blocks until the event is set, spinning at first and then yielding to
+    /// the scheduler.
+    pub fn wait(&self) {
+        let mut backoff = Backoff::with_jitter();
+        while !self.is_set() {
+            backoff.snooze();
+        }
+    }
+
+    /// `wait` that gives up after `timeout`. false means it timed out.
+    #[cfg(feature = "std")]
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let start = Instant::now();
+        let mut backoff = Backoff::with_jitter();
+        while !self.is_set() {
+            if start.elapsed() >= timeout {
+                return false;
+            }
+            backoff.snooze();
+        }
+        true
+    }
+}
+
+impl Default for Event {
+    fn default() -> Self {
+        Self::new()
+    }
+}
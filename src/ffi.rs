@@ -0,0 +1,75 @@
+//! C bindings for `RawSpinLock`, with the `ffi` feature, so C code can share
+//! state with Rust under the same lock. The header is `include/spinlock.h`;
+//! after changing the bindings, regenerate it with
+//!
+//! ```text
+//! SPINLOCK_REGENERATE_HEADER=1 cargo build --features ffi
+//! ```
+//!
+//! and a library to link against can be built with
+//!
+//! ```text
+//! cargo rustc --release --features ffi --crate-type staticlib
+//! ```
+//!
+//! A lock is an opaque `spinlock_t *` from `spinlock_new`, freed with
+//! `spinlock_destroy`. None of the functions accept NULL except
+//! `spinlock_destroy`.
+
+use alloc::boxed::Box;
+
+use crate::RawSpinLock;
+
+/// The lock behind a C `spinlock_t *`.
+pub struct SpinLockHandle(RawSpinLock);
+
+/// Allocates a new unlocked lock.
+#[no_mangle]
+pub extern "C" fn spinlock_new() -> *mut SpinLockHandle {
+    Box::into_raw(Box::new(SpinLockHandle(RawSpinLock::new())))
+}
+
+/// Frees a lock made by `spinlock_new`. NULL is ignored.
+///
+/// # Safety
+///
+/// `lock` must be NULL or come from `spinlock_new`, not be locked, and not
+/// be used by anyone after this.
+#[no_mangle]
+pub unsafe extern "C" fn spinlock_destroy(lock: *mut SpinLockHandle) {
+    if !lock.is_null() {
+        drop(Box::from_raw(lock));
+    }
+}
+
+/// Spins until the lock is ours.
+///
+/// # Safety
+///
+/// `lock` must come from `spinlock_new` and not be destroyed yet.
+#[no_mangle]
+pub unsafe extern "C" fn spinlock_lock(lock: *const SpinLockHandle) {
+    (*lock).0.lock()
+}
+
+/// Takes the lock if it's free right now, never spins. Returns whether it
+/// did.
+///
+/// # Safety
+///
+/// `lock` must come from `spinlock_new` and not be destroyed yet.
+#[no_mangle]
+pub unsafe extern "C" fn spinlock_try_lock(lock: *const SpinLockHandle) -> bool {
+    (*lock).0.try_lock()
+}
+
+/// Releases the lock.
+///
+/// # Safety
+///
+/// `lock` must come from `spinlock_new`, not be destroyed yet, and be held
+/// by the caller, from `spinlock_lock` or a successful `spinlock_try_lock`.
+#[no_mangle]
+pub unsafe extern "C" fn spinlock_unlock(lock: *const SpinLockHandle) {
+    (*lock).0.unlock()
+}
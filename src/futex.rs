@@ -0,0 +1,221 @@
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::AtomicU32;
+
+use crate::sync::{Acquire, Relaxed, Release};
+
+// Nobody holds the lock.
+const UNLOCKED: u32 = 0;
+// Held, and nobody is sleeping on it, so unlocking is a single store.
+const LOCKED: u32 = 1;
+// Held, and someone may be sleeping on it, so unlocking has to wake them.
+const CONTENDED: u32 = 2;
+
+// Spins before going to sleep in the kernel.
+const SPIN_LIMIT: u32 = 100;
+
+/// Three-state mutex that spins briefly and then sleeps in the OS
+/// (`futex` on Linux/Android, `WaitOnAddress` on Windows,
+/// `memory.atomic.wait32` on wasm32 with the `atomics` target feature), like
+/// `std::sync::Mutex` but with this crate's spinning fast path. On other
+/// platforms waiting falls back to spinning.
+///
+/// On wasm the contended path blocks, which browsers don't allow on the main
+/// thread; lock it from workers only there.
+pub struct FutexLock<T> {
+    state: AtomicU32,
+    pub(crate) value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for FutexLock<T> where T: Send {}
+
+impl<T> FutexLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            state: AtomicU32::new(UNLOCKED),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline]
+    pub fn lock(&self) -> FutexGuard<'_, T> {
+        if self
+            .state
+            .compare_exchange_weak(UNLOCKED, LOCKED, Acquire, Relaxed)
+            .is_err()
+        {
+            self.lock_slow();
+        }
+        FutexGuard {
+            lock: self,
+            marker: PhantomData,
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn lock_slow(&self) {
+        // Spin while the holder doesn't know about waiters, it may be on its
+        // way out already.
+        for _ in 0..SPIN_LIMIT {
+            match self
+                .state
+                .compare_exchange_weak(UNLOCKED, LOCKED, Acquire, Relaxed)
+            {
+                Ok(_) => return,
+                Err(CONTENDED) => break,
+                Err(_) => core::hint::spin_loop(),
+            }
+        }
+        // From here on we might sleep, so mark the lock contended. If we take
+        // it this way it stays marked, as there may be other sleepers.
+        while self.state.swap(CONTENDED, Acquire) != UNLOCKED {
+            sys::wait(&self.state, CONTENDED);
+        }
+    }
+
+    #[inline]
+    pub fn try_lock(&self) -> Option<FutexGuard<'_, T>> {
+        self.state
+            .compare_exchange(UNLOCKED, LOCKED, Acquire, Relaxed)
+            .ok()
+            .map(|_| FutexGuard {
+                lock: self,
+                marker: PhantomData,
+            })
+    }
+
+    #[inline]
+    fn unlock(&self) {
+        if self.state.swap(UNLOCKED, Release) == CONTENDED {
+            sys::wake_one(&self.state);
+        }
+    }
+}
+
+pub struct FutexGuard<'a, T> {
+    pub(crate) lock: &'a FutexLock<T>,
+    // Send and Sync are implemented below rather than following `lock`
+    pub(crate) marker: PhantomData<*const ()>,
+}
+
+unsafe impl<T: Send> Send for FutexGuard<'_, T> {}
+unsafe impl<T: Sync> Sync for FutexGuard<'_, T> {}
+
+impl<T> Deref for FutexGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: very existence of guard
+        // guarantees we've exclusively locked the lock
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for FutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: very existence of guard
+        // guarantees we've exclusively locked the lock
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for FutexGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.unlock();
+    }
+}
+
+// wait() returns once the value might no longer be `expected` (spurious
+// returns are fine, callers re-check), wake_one() wakes at least one waiter.
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod sys {
+    use core::ptr;
+    use core::sync::atomic::AtomicU32;
+
+    pub(crate) fn wait(state: &AtomicU32, expected: u32) {
+        // safety: the kernel only reads the word, and returns right away if it
+        // no longer holds `expected`
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                state.as_ptr(),
+                libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+                expected,
+                ptr::null::<libc::timespec>(),
+            );
+        }
+    }
+
+    pub(crate) fn wake_one(state: &AtomicU32) {
+        // safety: waking on an address nobody waits on is a no-op
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                state.as_ptr(),
+                libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG,
+                1,
+            );
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use core::ffi::c_void;
+    use core::sync::atomic::AtomicU32;
+    use windows_sys::Win32::System::Threading::{WaitOnAddress, WakeByAddressSingle, INFINITE};
+
+    pub(crate) fn wait(state: &AtomicU32, expected: u32) {
+        // safety: both pointers are valid for 4 bytes for the whole call
+        unsafe {
+            WaitOnAddress(
+                state.as_ptr() as *const c_void,
+                &expected as *const u32 as *const c_void,
+                4,
+                INFINITE,
+            );
+        }
+    }
+
+    pub(crate) fn wake_one(state: &AtomicU32) {
+        // safety: waking on an address nobody waits on is a no-op
+        unsafe { WakeByAddressSingle(state.as_ptr() as *const c_void) };
+    }
+}
+
+// Blocking like this traps on a browser's main thread, only lock from workers.
+#[cfg(all(target_arch = "wasm32", target_feature = "atomics"))]
+mod sys {
+    use core::arch::wasm32;
+    use core::sync::atomic::AtomicU32;
+
+    pub(crate) fn wait(state: &AtomicU32, expected: u32) {
+        // safety: the pointer is valid and aligned for the whole call, and a
+        // negative timeout waits until notified
+        unsafe { wasm32::memory_atomic_wait32(state.as_ptr() as *mut i32, expected as i32, -1) };
+    }
+
+    pub(crate) fn wake_one(state: &AtomicU32) {
+        // safety: the pointer is valid and aligned
+        unsafe { wasm32::memory_atomic_notify(state.as_ptr() as *mut i32, 1) };
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    windows,
+    all(target_arch = "wasm32", target_feature = "atomics")
+)))]
+mod sys {
+    use core::sync::atomic::AtomicU32;
+
+    pub(crate) fn wait(_state: &AtomicU32, _expected: u32) {
+        core::hint::spin_loop();
+    }
+
+    pub(crate) fn wake_one(_state: &AtomicU32) {}
+}
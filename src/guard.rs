@@ -0,0 +1,312 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+
+use crate::{Backoff, RawSpinLock, Relax, SpinLock};
+
+/// Held lock on a `SpinLock`, unlocks when dropped.
+///
+/// A guard can be sent to another thread, which then unlocks: a spinlock
+/// doesn't care who releases it (but see `RawSpinLock` on `debug-detect`).
+///
+/// ```
+/// let lock = spinlock::SpinLock::new(0);
+/// std::thread::scope(|s| {
+///     let mut guard = lock.lock();
+///     s.spawn(move || *guard += 1);
+/// });
+/// ```
+///
+/// Sharing a guard between threads shares the value, so unlike the lock
+/// that needs the value to be `Sync`:
+///
+/// ```compile_fail
+/// fn share<T: Sync>(_: T) {}
+/// let lock = spinlock::SpinLock::new(core::cell::Cell::new(0));
+/// share(lock.lock());
+/// ```
+///
+/// Formatting a guard formats the value, so it can go straight into `dbg!`
+/// or a log line:
+///
+/// ```
+/// let lock = spinlock::SpinLock::new(vec![1, 2]);
+/// assert_eq!(format!("{:?}", lock.lock()), "[1, 2]");
+/// ```
+pub struct Guard<'a, T: ?Sized, R = Backoff> {
+    pub(crate) lock: &'a SpinLock<T, R>,
+    // Send and Sync are implemented below rather than following `lock`
+    pub(crate) marker: PhantomData<*const ()>,
+}
+
+unsafe impl<T: ?Sized + Send, R> Send for Guard<'_, T, R> {}
+unsafe impl<T: ?Sized + Sync, R> Sync for Guard<'_, T, R> {}
+
+impl<'a, T: ?Sized, R> Guard<'a, T, R> {
+    /// Releases the lock by consuming the guard, so no reference into the
+    /// protected value can outlive the unlock.
+    ///
+    /// Unlocking through the lock itself while a guard is alive is not possible:
+    ///
+    /// ```compile_fail
+    /// let lock = spinlock::SpinLock::new(0);
+    /// let g = lock.lock();
+    /// lock.unlock();
+    /// ```
+    ///
+    /// and the guard can't be used once it has been unlocked:
+    ///
+    /// ```compile_fail
+    /// let lock = spinlock::SpinLock::new(0);
+    /// let mut g = lock.lock();
+    /// g.unlock();
+    /// *g += 1;
+    /// ```
+    pub fn unlock(self) {
+        drop(self)
+    }
+
+    /// Narrows the guard down to one part of the protected value, e.g. a
+    /// single field, keeping the lock held. An associated function rather
+    /// than a method so it doesn't shadow a `map` on `T`:
+    /// `Guard::map(guard, |s| &mut s.field)`.
+    pub fn map<U: ?Sized, F>(mut this: Self, f: F) -> MappedGuard<'a, U, R>
+    where
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        let lock = this.lock;
+        // if f panics, `this` still unlocks on the way out
+        let value: *mut U = f(&mut *this);
+        mem::forget(this);
+        MappedGuard {
+            raw: &lock.raw,
+            value,
+            marker: PhantomData,
+        }
+    }
+
+    /// Like `map`, for parts that may not be there (an `Option`, an enum
+    /// variant). If `f` returns `None` the original guard comes back as the
+    /// error, still locked.
+    pub fn try_map<U: ?Sized, F>(mut this: Self, f: F) -> Result<MappedGuard<'a, U, R>, Self>
+    where
+        F: FnOnce(&mut T) -> Option<&mut U>,
+    {
+        let lock = this.lock;
+        let value: *mut U = match f(&mut *this) {
+            Some(value) => value,
+            None => return Err(this),
+        };
+        mem::forget(this);
+        Ok(MappedGuard {
+            raw: &lock.raw,
+            value,
+            marker: PhantomData,
+        })
+    }
+
+    /// Forgets the guard and leaves the lock held for good, for data that's
+    /// set up once and then only used through the returned reference.
+    /// Locking it again spins forever, and in debug builds dropping the lock
+    /// panics, so it's meant for locks that live forever anyway.
+    pub fn leak(this: Self) -> &'a mut T {
+        let lock = this.lock;
+        mem::forget(this);
+        // safety: the lock is never released, so nobody else can get at it
+        unsafe { &mut *lock.value.get() }
+    }
+}
+
+impl<T: ?Sized, R: Relax> Guard<'_, T, R> {
+    /// Unlocks and locks again, giving waiters a chance to get in during a
+    /// long loop that holds the lock. A plain spinlock isn't fair, so this
+    /// thread may well win the lock straight back; `TicketGuard::bump` and
+    /// `McsGuard::bump` always let the queued waiters go first.
+    pub fn bump(this: &mut Self) {
+        // safety: the guard is the proof we hold the lock, and it keeps not
+        // being used until we hold it again
+        unsafe { this.lock.raw.unlock() };
+        crate::sync::spin_loop();
+        this.lock.raw.lock();
+    }
+
+    /// Unlocks around `f`, e.g. to call back into user code or do I/O, and
+    /// locks again before returning, also if `f` panics. The guard is
+    /// borrowed for the duration, so `f` can't touch the value:
+    ///
+    /// ```compile_fail
+    /// let lock = spinlock::SpinLock::new(0);
+    /// let mut g = lock.lock();
+    /// spinlock::Guard::unlocked(&mut g, || *g += 1);
+    /// ```
+    pub fn unlocked<U, F: FnOnce() -> U>(this: &mut Self, f: F) -> U {
+        struct Relock<'a, R: Relax>(&'a RawSpinLock<R>);
+        impl<R: Relax> Drop for Relock<'_, R> {
+            fn drop(&mut self) {
+                self.0.lock();
+            }
+        }
+
+        // safety: the guard is the proof we hold the lock, and it keeps not
+        // being used until Relock took it again
+        unsafe { this.lock.raw.unlock() };
+        let _relock = Relock(&this.lock.raw);
+        f()
+    }
+}
+
+impl<T: ?Sized, R> Deref for Guard<'_, T, R> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: very existence of guard
+        // garuntees we've exclusively locked the lock
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized, R> DerefMut for Guard<'_, T, R> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: very existence of guard
+        // garuntees we've exclusively locked the lock
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+// So a guard can go straight to APIs generic over these, without `&*guard`.
+impl<T: ?Sized, R> AsRef<T> for Guard<'_, T, R> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized, R> AsMut<T> for Guard<'_, T, R> {
+    fn as_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T: ?Sized, R> Borrow<T> for Guard<'_, T, R> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized + fmt::Debug, R> fmt::Debug for Guard<'_, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display, R> fmt::Display for Guard<'_, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized, R> Drop for Guard<'_, T, R> {
+    #[inline]
+    fn drop(&mut self) {
+        // safety: the guard is the proof we hold the lock, and it's going away
+        unsafe { self.lock.raw.unlock() }
+    }
+}
+
+/// A guard for part of a `SpinLock`'s value, made by `Guard::map`.
+pub struct MappedGuard<'a, U: ?Sized, R = Backoff> {
+    pub(crate) raw: &'a RawSpinLock<R>,
+    pub(crate) value: *mut U,
+    pub(crate) marker: PhantomData<&'a mut U>,
+}
+
+unsafe impl<U: ?Sized + Send, R> Send for MappedGuard<'_, U, R> {}
+unsafe impl<U: ?Sized + Sync, R> Sync for MappedGuard<'_, U, R> {}
+
+impl<'a, U: ?Sized, R> MappedGuard<'a, U, R> {
+    /// Narrows the guard further, like `Guard::map`.
+    pub fn map<V: ?Sized, F>(mut this: Self, f: F) -> MappedGuard<'a, V, R>
+    where
+        F: FnOnce(&mut U) -> &mut V,
+    {
+        let raw = this.raw;
+        let value: *mut V = f(&mut *this);
+        mem::forget(this);
+        MappedGuard {
+            raw,
+            value,
+            marker: PhantomData,
+        }
+    }
+
+    /// Narrows the guard further, like `Guard::try_map`.
+    pub fn try_map<V: ?Sized, F>(mut this: Self, f: F) -> Result<MappedGuard<'a, V, R>, Self>
+    where
+        F: FnOnce(&mut U) -> Option<&mut V>,
+    {
+        let raw = this.raw;
+        let value: *mut V = match f(&mut *this) {
+            Some(value) => value,
+            None => return Err(this),
+        };
+        mem::forget(this);
+        Ok(MappedGuard {
+            raw,
+            value,
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<U: ?Sized, R> Deref for MappedGuard<'_, U, R> {
+    type Target = U;
+    fn deref(&self) -> &U {
+        // safety: points into the locked value, and we still hold the lock
+        unsafe { &*self.value }
+    }
+}
+
+impl<U: ?Sized, R> DerefMut for MappedGuard<'_, U, R> {
+    fn deref_mut(&mut self) -> &mut U {
+        // safety: points into the locked value, and we still hold the lock
+        unsafe { &mut *self.value }
+    }
+}
+
+impl<U: ?Sized, R> AsRef<U> for MappedGuard<'_, U, R> {
+    fn as_ref(&self) -> &U {
+        self
+    }
+}
+
+impl<U: ?Sized, R> AsMut<U> for MappedGuard<'_, U, R> {
+    fn as_mut(&mut self) -> &mut U {
+        self
+    }
+}
+
+impl<U: ?Sized, R> Borrow<U> for MappedGuard<'_, U, R> {
+    fn borrow(&self) -> &U {
+        self
+    }
+}
+
+impl<U: ?Sized + fmt::Debug, R> fmt::Debug for MappedGuard<'_, U, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<U: ?Sized + fmt::Display, R> fmt::Display for MappedGuard<'_, U, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<U: ?Sized, R> Drop for MappedGuard<'_, U, R> {
+    #[inline]
+    fn drop(&mut self) {
+        // safety: the guard is the proof we hold the lock, and it's going away
+        unsafe { self.raw.unlock() }
+    }
+}
@@ -0,0 +1,75 @@
+use core::cell::UnsafeCell;
+use core::ptr;
+
+use crate::{Backoff, Guard, SpinLock};
+
+/// Data protected by a `SpinLock` it can't live inside, e.g. because it's
+/// split across several structs. Getting at it takes a guard of that lock:
+/// the lock's value type `L` (typically a unit struct used as a tag) is
+/// checked at compile time, the lock instance when presenting the guard.
+///
+/// ```
+/// use spinlock::{GuardedBy, SpinLock};
+///
+/// struct Accounts;
+///
+/// let lock = SpinLock::new(Accounts);
+/// let balance = GuardedBy::new(&lock, 100);
+/// let history = GuardedBy::new(&lock, Vec::new());
+///
+/// let mut guard = lock.lock();
+/// *balance.get_mut(&mut guard) -= 30;
+/// history.get_mut(&mut guard).push(-30);
+/// assert_eq!(*balance.get(&guard), 70);
+/// ```
+pub struct GuardedBy<'a, T: ?Sized, L: ?Sized, R = Backoff> {
+    lock: &'a SpinLock<L, R>,
+    value: UnsafeCell<T>,
+}
+
+// Access is serialized by the lock, but `get` hands out `&T` to whoever can
+// see the guard, possibly several threads at once.
+unsafe impl<T: ?Sized + Send + Sync, L: ?Sized + Send, R> Sync for GuardedBy<'_, T, L, R> {}
+
+impl<'a, T, L: ?Sized, R> GuardedBy<'a, T, L, R> {
+    pub const fn new(lock: &'a SpinLock<L, R>, value: T) -> Self {
+        Self {
+            lock,
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<'a, T: ?Sized, L: ?Sized, R> GuardedBy<'a, T, L, R> {
+    /// Panics if `guard` is for a different lock than this data's.
+    pub fn get<'g>(&'g self, guard: &'g Guard<'_, L, R>) -> &'g T {
+        self.check(guard);
+        // safety: the guard proves the lock is held, and borrowing it keeps
+        // get_mut from being called until this reference is gone
+        unsafe { &*self.value.get() }
+    }
+
+    /// Panics if `guard` is for a different lock than this data's.
+    pub fn get_mut<'g>(&'g self, guard: &'g mut Guard<'_, L, R>) -> &'g mut T {
+        self.check(guard);
+        // safety: the lock is held, and the guard is borrowed exclusively
+        // for as long as the reference lives
+        unsafe { &mut *self.value.get() }
+    }
+
+    /// The data without locking, for when nobody else can get at it anyway.
+    pub fn get_unguarded_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    fn check(&self, guard: &Guard<'_, L, R>) {
+        assert!(
+            ptr::eq(guard.lock, self.lock),
+            "guard of a different lock than the one protecting this data"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+use crate::{Guard, SpinLock};
+
+#[cfg(all(debug_assertions, feature = "std"))]
+mod held {
+    use std::cell::RefCell;
+    use std::vec::Vec;
+
+    std::thread_local! {
+        // levels of the hierarchical locks this thread holds, in locking order
+        static HELD: RefCell<Vec<u32>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub(super) fn check(level: u32) {
+        HELD.with(|held| {
+            if let Some(&lowest) = held.borrow().iter().min() {
+                assert!(
+                    level < lowest,
+                    "lock order violation: locking level {level} while holding level {lowest}"
+                );
+            }
+        });
+    }
+
+    pub(super) fn push(level: u32) {
+        HELD.with(|held| held.borrow_mut().push(level));
+    }
+
+    pub(super) fn pop(level: u32) {
+        HELD.with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(i) = held.iter().rposition(|&l| l == level) {
+                held.remove(i);
+            }
+        });
+    }
+}
+
+/// Spinlock with a level in a lock hierarchy: while holding a lock, a thread
+/// may only lock others with a strictly lower level. In debug builds with
+/// `std`, breaking that order panics, catching potential deadlocks even when
+/// the threads involved happen not to collide. Release builds don't check.
+///
+/// `try_lock` can't deadlock, so it's exempt from the check.
+pub struct HierarchicalSpinLock<T: ?Sized> {
+    level: u32,
+    inner: SpinLock<T>,
+}
+
+impl<T> HierarchicalSpinLock<T> {
+    pub const fn new(level: u32, value: T) -> Self {
+        Self {
+            level,
+            inner: SpinLock::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> HierarchicalSpinLock<T> {
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    #[inline]
+    pub fn lock(&self) -> HierarchicalGuard<'_, T> {
+        #[cfg(all(debug_assertions, feature = "std"))]
+        held::check(self.level);
+        self.wrap(self.inner.lock())
+    }
+
+    #[inline]
+    pub fn try_lock(&self) -> Option<HierarchicalGuard<'_, T>> {
+        self.inner.try_lock().map(|guard| self.wrap(guard))
+    }
+
+    fn wrap<'a>(&'a self, guard: Guard<'a, T>) -> HierarchicalGuard<'a, T> {
+        #[cfg(all(debug_assertions, feature = "std"))]
+        held::push(self.level);
+        HierarchicalGuard {
+            #[cfg(all(debug_assertions, feature = "std"))]
+            level: self.level,
+            guard,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+}
+
+pub struct HierarchicalGuard<'a, T: ?Sized> {
+    #[cfg(all(debug_assertions, feature = "std"))]
+    level: u32,
+    guard: Guard<'a, T>,
+    // the held levels are tracked per thread, so the guard must stay on it
+    marker: PhantomData<*const ()>,
+}
+
+impl<T: ?Sized> Deref for HierarchicalGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for HierarchicalGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized> Drop for HierarchicalGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        #[cfg(all(debug_assertions, feature = "std"))]
+        held::pop(self.level);
+    }
+}
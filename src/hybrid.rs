@@ -0,0 +1,242 @@
+use core::cell::{Cell, UnsafeCell};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering::SeqCst};
+use std::thread::{self, Thread};
+
+use crate::sync::{Acquire, Relaxed, Release};
+use crate::{clock, Backoff, SpinLock};
+
+// A waiter spins for up to twice the typical hold, but never longer than
+// this: past it parking costs less than the spinning it saves. Holds expected
+// to be longer than this park right away.
+const MAX_SPIN_NS: u32 = 20_000;
+
+// Holds shorter than this are treated as this long, so there's always a
+// little spinning before a lock that's normally quick parks anyone.
+const MIN_SPIN_NS: u32 = 500;
+
+// Lives on the stack of a parked thread, linked into the lock's queue. It is
+// only unlinked by the waker, before `notified` is set, and the owner doesn't
+// return until it sees `notified`, so the queue never holds a dangling node.
+struct Waiter {
+    thread: Thread,
+    notified: AtomicBool,
+    next: Cell<*const Waiter>,
+}
+
+struct WaitQueue {
+    head: *const Waiter,
+    tail: *const Waiter,
+}
+
+// The raw pointers are only followed while holding the queue's spinlock.
+unsafe impl Send for WaitQueue {}
+
+/// Mutex that spins for a short while like `SpinLock`, then parks the thread
+/// until the holder wakes it, so long critical sections don't burn a core
+/// per waiter. Unlocking wakes one parked waiter.
+///
+/// How long to spin adapts to the lock, like an adaptive OS mutex: holds
+/// that began while someone was waiting are timed into a moving average,
+/// waiters spin for about twice that, and if holds run to tens of
+/// microseconds they park without spinning at all. Uncontended locking
+/// never reads the clock.
+pub struct HybridMutex<T> {
+    locked: AtomicBool,
+    // moving average of timed holds, in nanoseconds. Written by holders only.
+    hold_ns: AtomicU32,
+    // when the current hold began if it's being timed, else 0. Owner only.
+    since: AtomicU64,
+    // parked (or about to park) threads, so unlock can skip the queue when zero
+    waiters: AtomicUsize,
+    queue: SpinLock<WaitQueue>,
+    pub(crate) value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for HybridMutex<T> where T: Send {}
+
+impl<T> HybridMutex<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            hold_ns: AtomicU32::new(0),
+            since: AtomicU64::new(0),
+            waiters: AtomicUsize::new(0),
+            queue: SpinLock::new(WaitQueue {
+                head: ptr::null(),
+                tail: ptr::null(),
+            }),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline]
+    pub fn lock(&self) -> HybridGuard<'_, T> {
+        if self
+            .locked
+            .compare_exchange_weak(false, true, Acquire, Relaxed)
+            .is_err()
+        {
+            self.lock_slow();
+        }
+        HybridGuard {
+            lock: self,
+            marker: PhantomData,
+        }
+    }
+
+    // Contended, so whatever the hold we start here is worth timing.
+    #[cold]
+    #[inline(never)]
+    fn lock_slow(&self) {
+        self.wait();
+        self.since.store(clock::now().max(1), Relaxed);
+    }
+
+    fn wait(&self) {
+        let hold = self.hold_ns.load(Relaxed);
+        if hold <= MAX_SPIN_NS {
+            let budget = u64::from(hold.max(MIN_SPIN_NS)) * 2;
+            let start = clock::now();
+            let mut backoff = Backoff::with_jitter();
+            while clock::now() - start < budget {
+                if !self.locked.load(Relaxed) && self.acquire() {
+                    return;
+                }
+                backoff.spin();
+            }
+        }
+        loop {
+            let waiter = Waiter {
+                thread: thread::current(),
+                notified: AtomicBool::new(false),
+                next: Cell::new(ptr::null()),
+            };
+            {
+                let mut queue = self.queue.lock();
+                // Announce ourselves before the last attempt. Paired with the
+                // unlocker's store-then-load this means either we see the lock
+                // free here, or the unlocker sees us and comes to wake us.
+                self.waiters.fetch_add(1, SeqCst);
+                if self.acquire() {
+                    self.waiters.fetch_sub(1, Relaxed);
+                    return;
+                }
+                let node: *const Waiter = &waiter;
+                if queue.tail.is_null() {
+                    queue.head = node;
+                } else {
+                    // safety: queued nodes stay alive until they're popped
+                    unsafe { (*queue.tail).next.set(node) };
+                }
+                queue.tail = node;
+            }
+            while !waiter.notified.load(Acquire) {
+                thread::park();
+            }
+            // woken, but a spinning thread may have barged in ahead of us
+            if self.acquire() {
+                return;
+            }
+        }
+    }
+
+    #[inline]
+    fn acquire(&self) -> bool {
+        self.locked
+            .compare_exchange(false, true, SeqCst, Relaxed)
+            .is_ok()
+    }
+
+    pub fn try_lock(&self) -> Option<HybridGuard<'_, T>> {
+        self.locked
+            .compare_exchange(false, true, Acquire, Relaxed)
+            .ok()
+            .map(|_| HybridGuard {
+                lock: self,
+                marker: PhantomData,
+            })
+    }
+
+    #[inline]
+    fn unlock(&self) {
+        let since = self.since.load(Relaxed);
+        if since != 0 {
+            self.record_hold(since);
+        }
+        self.locked.store(false, SeqCst);
+        if self.waiters.load(SeqCst) != 0 {
+            self.wake_one();
+        }
+    }
+
+    // Folds a timed hold into the average, still holding the lock so no two
+    // holders update it at once.
+    #[cold]
+    #[inline(never)]
+    fn record_hold(&self, since: u64) {
+        self.since.store(0, Relaxed);
+        let held = (clock::now() - since).min(u64::from(u32::MAX)) as u32;
+        let avg = self.hold_ns.load(Relaxed);
+        self.hold_ns.store(avg - avg / 8 + held / 8, Relaxed);
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn wake_one(&self) {
+        let waiter = {
+            let mut queue = self.queue.lock();
+            let head = queue.head;
+            if head.is_null() {
+                return;
+            }
+            // safety: queued nodes stay alive until they're popped
+            queue.head = unsafe { (*head).next.get() };
+            if queue.head.is_null() {
+                queue.tail = ptr::null();
+            }
+            self.waiters.fetch_sub(1, Relaxed);
+            head
+        };
+        // safety: still alive, its owner can't leave before `notified` is set.
+        // Grab the handle first, the node may be gone right after the store.
+        let thread = unsafe { (*waiter).thread.clone() };
+        unsafe { (*waiter).notified.store(true, Release) };
+        thread.unpark();
+    }
+}
+
+pub struct HybridGuard<'a, T> {
+    pub(crate) lock: &'a HybridMutex<T>,
+    // Send and Sync are implemented below rather than following `lock`
+    pub(crate) marker: PhantomData<*const ()>,
+}
+
+unsafe impl<T: Send> Send for HybridGuard<'_, T> {}
+unsafe impl<T: Sync> Sync for HybridGuard<'_, T> {}
+
+impl<T> Deref for HybridGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: very existence of guard
+        // guarantees we've exclusively locked the lock
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for HybridGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: very existence of guard
+        // guarantees we've exclusively locked the lock
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for HybridGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.unlock();
+    }
+}
@@ -0,0 +1,26 @@
+use core::time::Duration;
+
+// Events for the `tracing` feature go to the "spinlock" target at trace
+// level, naming the lock if it has a name and giving its address either way.
+
+#[inline]
+pub(crate) fn contended(name: Option<&'static str>, addr: *const (), wait: Duration) {
+    tracing::trace!(
+        target: "spinlock",
+        lock = name.unwrap_or("<unnamed>"),
+        ?addr,
+        ?wait,
+        "contended lock acquired"
+    );
+}
+
+#[inline]
+pub(crate) fn released(name: Option<&'static str>, addr: *const (), hold: Duration) {
+    tracing::trace!(
+        target: "spinlock",
+        lock = name.unwrap_or("<unnamed>"),
+        ?addr,
+        ?hold,
+        "lock released"
+    );
+}
@@ -0,0 +1,178 @@
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+use crate::RawSpinLock;
+
+/// How `IrqSpinLock` masks interrupts on the current core. The crate can't
+/// know the platform's way of doing that, so it's up to the user, e.g. on
+/// Cortex-M with the `cortex-m` crate:
+///
+/// ```ignore
+/// struct CortexM;
+///
+/// unsafe impl spinlock::Interrupts for CortexM {
+///     type State = bool;
+///
+///     fn disable() -> bool {
+///         let was_enabled = cortex_m::register::primask::read().is_active();
+///         cortex_m::interrupt::disable();
+///         was_enabled
+///     }
+///
+///     unsafe fn restore(was_enabled: bool) {
+///         if was_enabled {
+///             cortex_m::interrupt::enable();
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Safety
+///
+/// `disable` must really keep interrupt handlers that might take the lock
+/// off the current core until `restore`, or they can deadlock against it.
+pub unsafe trait Interrupts {
+    /// Whatever `restore` needs to put things back the way they were,
+    /// usually whether interrupts were enabled.
+    type State;
+
+    /// Masks interrupts, returning the previous state.
+    fn disable() -> Self::State;
+
+    /// # Safety
+    ///
+    /// `state` must come from the matching `disable` on this core, restored
+    /// in reverse order of disabling when nested.
+    unsafe fn restore(state: Self::State);
+}
+
+/// Spinlock for data shared with interrupt handlers. Interrupts stay masked
+/// on the locking core from before the lock is taken until after it's
+/// released, so a handler can never spin on a lock its own core holds.
+pub struct IrqSpinLock<T: ?Sized, I> {
+    raw: RawSpinLock,
+    // fn() so the strategy doesn't affect Send/Sync, it's never stored
+    interrupts: PhantomData<fn() -> I>,
+    pub(crate) value: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send, I> Sync for IrqSpinLock<T, I> {}
+
+impl<T, I> IrqSpinLock<T, I> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            raw: RawSpinLock::new(),
+            interrupts: PhantomData,
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized, I: Interrupts> IrqSpinLock<T, I> {
+    #[inline]
+    pub fn lock(&self) -> IrqGuard<'_, T, I> {
+        let state = I::disable();
+        self.raw.lock();
+        IrqGuard {
+            lock: self,
+            state: Some(state),
+            marker: PhantomData,
+        }
+    }
+
+    // Single attempt, never spins. Interrupts are left as they were if it
+    // fails.
+    #[inline]
+    pub fn try_lock(&self) -> Option<IrqGuard<'_, T, I>> {
+        let state = I::disable();
+        if self.raw.try_lock() {
+            Some(IrqGuard {
+                lock: self,
+                state: Some(state),
+                marker: PhantomData,
+            })
+        } else {
+            // safety: straight from the disable above
+            unsafe { I::restore(state) };
+            None
+        }
+    }
+}
+
+impl<T: ?Sized, I> IrqSpinLock<T, I> {
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+pub struct IrqGuard<'a, T: ?Sized, I: Interrupts> {
+    lock: &'a IrqSpinLock<T, I>,
+    // only None while dropping
+    state: Option<I::State>,
+    // the interrupt state belongs to this core, so the guard must stay on it
+    marker: PhantomData<*const ()>,
+}
+
+impl<T: ?Sized, I: Interrupts> Deref for IrqGuard<'_, T, I> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: very existence of guard
+        // garuntees we've exclusively locked the lock
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized, I: Interrupts> DerefMut for IrqGuard<'_, T, I> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: very existence of guard
+        // garuntees we've exclusively locked the lock
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized, I: Interrupts> Drop for IrqGuard<'_, T, I> {
+    #[inline]
+    fn drop(&mut self) {
+        // Unlock before unmasking, or a handler could come in and spin on the
+        // lock we still hold.
+        // safety: the guard is the proof we hold the lock
+        unsafe { self.lock.raw.unlock() };
+        if let Some(state) = self.state.take() {
+            // safety: taken by the disable in lock/try_lock on this core
+            unsafe { I::restore(state) };
+        }
+    }
+}
+
+/// `Interrupts` through the `critical-section` crate, for single-core
+/// targets where plain spinning can never win against an interrupt handler
+/// holding the lock. Works on any target with a `critical-section`
+/// implementation linked in.
+#[cfg(feature = "critical-section")]
+pub struct CriticalSection;
+
+#[cfg(feature = "critical-section")]
+unsafe impl Interrupts for CriticalSection {
+    type State = critical_section::RestoreState;
+
+    #[inline]
+    fn disable() -> Self::State {
+        // safety: every acquire is paired with a release by IrqGuard's drop,
+        // or straight away in try_lock
+        unsafe { critical_section::acquire() }
+    }
+
+    #[inline]
+    unsafe fn restore(state: Self::State) {
+        critical_section::release(state)
+    }
+}
+
+/// `IrqSpinLock` that holds the global critical section while locked.
+#[cfg(feature = "critical-section")]
+pub type CriticalSectionSpinLock<T> = IrqSpinLock<T, CriticalSection>;
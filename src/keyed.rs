@@ -0,0 +1,91 @@
+use core::borrow::Borrow;
+use core::hash::Hash;
+use std::collections::HashSet;
+
+use crate::{Backoff, ShardedSpinLock};
+
+/// Mutual exclusion per key, e.g. per user id or file path, without a lock
+/// per key made up front. The keys currently held are kept in `N`
+/// spinlocked sets, so a key only takes memory while it's locked, and
+/// threads locking keys in different shards never meet. Keys that share a
+/// shard (or even a hash) still exclude each other only if they're equal.
+///
+/// Waiters aren't queued, so under heavy contention on one key a thread can
+/// be overtaken indefinitely.
+///
+/// ```
+/// let users = spinlock::KeyedLock::<u64>::new();
+/// let alice = users.lock(1);
+/// assert!(users.try_lock(1).is_none());
+/// assert!(users.try_lock(2).is_some());
+/// drop(alice);
+/// assert!(users.try_lock(1).is_some());
+/// ```
+pub struct KeyedLock<K, const N: usize = 16> {
+    held: ShardedSpinLock<HashSet<K>, N>,
+}
+
+impl<K: Hash + Eq + Clone, const N: usize> KeyedLock<K, N> {
+    pub fn new() -> Self {
+        Self {
+            held: ShardedSpinLock::from_fn(|_| HashSet::new()),
+        }
+    }
+
+    /// Waits until no one else holds `key`, then holds it until the guard
+    /// is dropped.
+    pub fn lock(&self, key: K) -> KeyGuard<'_, K, N> {
+        let mut backoff = Backoff::with_jitter();
+        loop {
+            // the shard is only locked to look, never while waiting
+            if self.insert(&key) {
+                return KeyGuard { lock: self, key };
+            }
+            backoff.relax();
+        }
+    }
+
+    // Single attempt, never waits. None means someone else holds the key.
+    pub fn try_lock(&self, key: K) -> Option<KeyGuard<'_, K, N>> {
+        self.insert(&key).then(|| KeyGuard { lock: self, key })
+    }
+
+    /// Whether someone holds `key`. Only a snapshot, may be stale by the
+    /// time the caller looks at it.
+    pub fn is_locked<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.held.get(key).lock().contains(key)
+    }
+
+    fn insert(&self, key: &K) -> bool {
+        let mut held = self.held.get(key).lock();
+        !held.contains(key) && held.insert(key.clone())
+    }
+}
+
+impl<K: Hash + Eq + Clone, const N: usize> Default for KeyedLock<K, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One key held in a `KeyedLock`.
+pub struct KeyGuard<'a, K: Hash + Eq, const N: usize = 16> {
+    lock: &'a KeyedLock<K, N>,
+    key: K,
+}
+
+impl<K: Hash + Eq, const N: usize> KeyGuard<'_, K, N> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K: Hash + Eq, const N: usize> Drop for KeyGuard<'_, K, N> {
+    fn drop(&mut self) {
+        self.lock.held.get(&self.key).lock().remove(&self.key);
+    }
+}
@@ -0,0 +1,42 @@
+use core::sync::atomic::AtomicUsize;
+
+use crate::sync::{Acquire, Release};
+use crate::Backoff;
+
+/// Single-use countdown: `wait` blocks until `count_down` has been called `n`
+/// times. Unlike a `SpinBarrier`, the threads counting down and the ones
+/// waiting can be entirely different sets, and counting down never blocks.
+pub struct Latch {
+    count: AtomicUsize,
+}
+
+impl Latch {
+    /// A latch that's already open if `n` is 0.
+    pub const fn new(n: usize) -> Self {
+        Self {
+            count: AtomicUsize::new(n),
+        }
+    }
+
+    /// Once the count is at zero, further calls do nothing.
+    pub fn count_down(&self) {
+        let _ = self
+            .count
+            .fetch_update(Release, Acquire, |count| count.checked_sub(1));
+    }
+
+    /// Whether the count has reached zero, without waiting.
+    pub fn try_wait(&self) -> bool {
+        self.count.load(Acquire) == 0
+    }
+
+    /// Blocks until the count reaches zero, spinning at first and then
+    /// yielding to the scheduler. Everything done before each `count_down`
+    /// is visible afterwards.
+    pub fn wait(&self) {
+        let mut backoff = Backoff::with_jitter();
+        while !self.try_wait() {
+            backoff.snooze();
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use core::cell::Cell;
+use core::fmt;
+use core::ops::Deref;
+
+use crate::SpinOnce;
+
+/// A value built on first access, e.g.
+/// `static TABLE: Lazy<Vec<u8>> = Lazy::new(build_table);`.
+pub struct Lazy<T, F = fn() -> T> {
+    once: SpinOnce<T>,
+    init: Cell<Option<F>>,
+}
+
+// `init` is only taken by whichever thread wins the SpinOnce, so the Cell is
+// never touched concurrently.
+unsafe impl<T: Send + Sync, F: Send> Sync for Lazy<T, F> {}
+
+impl<T, F> Lazy<T, F> {
+    pub const fn new(init: F) -> Self {
+        Self {
+            once: SpinOnce::new(),
+            init: Cell::new(Some(init)),
+        }
+    }
+}
+
+impl<T, F: FnOnce() -> T> Lazy<T, F> {
+    /// Initializes the value if that hasn't happened yet. An associated
+    /// function so it doesn't shadow a `force` on `T`.
+    ///
+    /// If the initializer panics the value stays uninitialized, and since the
+    /// initializer is gone by then, every later access panics too.
+    #[inline]
+    pub fn force(this: &Self) -> &T {
+        this.once.get_or_init(|| match this.init.take() {
+            Some(init) => init(),
+            None => panic!("Lazy instance has previously been poisoned"),
+        })
+    }
+}
+
+impl<T, F: FnOnce() -> T> Deref for Lazy<T, F> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        Self::force(self)
+    }
+}
+
+impl<T: Default> Default for Lazy<T> {
+    fn default() -> Self {
+        Self::new(T::default)
+    }
+}
+
+// Never forces the value: prints `<uninit>` until someone else has.
+impl<T: fmt::Debug, F> fmt::Debug for Lazy<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_tuple("Lazy");
+        match self.once.get() {
+            Some(value) => d.field(value),
+            None => d.field(&format_args!("<uninit>")),
+        };
+        d.finish()
+    }
+}
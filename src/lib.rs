@@ -0,0 +1,169 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+mod async_condvar;
+#[cfg(feature = "alloc")]
+mod async_mutex;
+#[cfg(feature = "alloc")]
+mod async_rwlock;
+#[cfg(feature = "alloc")]
+mod async_semaphore;
+mod atomic_cell;
+mod backoff;
+mod barrier;
+#[cfg(feature = "std")]
+mod bravo;
+#[cfg(feature = "alloc")]
+mod clh;
+#[cfg(feature = "std")]
+mod clock;
+mod cohort;
+#[cfg(feature = "poison")]
+pub mod compat;
+mod condvar;
+#[cfg(feature = "deadlock_detection")]
+pub mod deadlock;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+#[cfg(feature = "elision")]
+mod elision;
+mod event;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "os-wait")]
+mod futex;
+mod guard;
+mod guarded;
+mod hierarchy;
+#[cfg(feature = "std")]
+mod hybrid;
+#[cfg(feature = "tracing")]
+mod instrument;
+mod irq;
+#[cfg(feature = "std")]
+mod keyed;
+mod latch;
+mod lazy;
+mod lock;
+#[cfg(feature = "lock_api")]
+pub mod lock_api;
+#[cfg(feature = "std")]
+mod map;
+mod mcs;
+mod multi;
+#[cfg(feature = "mutex-trait")]
+mod mutex_trait;
+mod once;
+mod ordered;
+#[cfg(feature = "alloc")]
+mod owned;
+mod padded;
+mod phase_fair;
+#[cfg(feature = "poison")]
+mod poison;
+mod priority;
+mod queue;
+mod raw;
+#[cfg(feature = "alloc")]
+mod rcu;
+#[cfg(feature = "registry")]
+pub mod registry;
+#[cfg(feature = "std")]
+mod reentrant;
+mod relax;
+mod rwlock;
+mod semaphore;
+mod seqcount;
+mod seqlock;
+#[cfg(feature = "serde")]
+mod serde;
+mod sharded;
+#[cfg(feature = "alloc")]
+pub mod spsc;
+#[cfg(feature = "stats")]
+mod stats;
+mod sync;
+#[cfg(feature = "std")]
+mod thread_id;
+mod ticket;
+mod timeout;
+mod wait_group;
+#[cfg(feature = "alloc")]
+mod waiters;
+#[cfg(feature = "watchdog")]
+pub mod watchdog;
+
+#[cfg(feature = "alloc")]
+pub use async_condvar::{AsyncCondvar, WaitFuture};
+#[cfg(feature = "alloc")]
+pub use async_mutex::{AsyncMutex, AsyncMutexGuard, LockFuture};
+#[cfg(feature = "alloc")]
+pub use async_rwlock::{AsyncReadGuard, AsyncRwLock, AsyncWriteGuard, ReadFuture, WriteFuture};
+#[cfg(feature = "alloc")]
+pub use async_semaphore::{
+    AcquireFuture, AsyncPermit, AsyncSemaphore, OwnedAcquireFuture, OwnedPermit,
+};
+pub use atomic_cell::AtomicCell;
+pub use backoff::{configure, Backoff, BackoffConfig};
+pub use barrier::SpinBarrier;
+#[cfg(feature = "std")]
+pub use bravo::{BravoReadGuard, BravoRwLock, BravoWriteGuard};
+#[cfg(feature = "alloc")]
+pub use clh::{ClhGuard, ClhLock};
+pub use cohort::{CohortGuard, CohortLock};
+pub use condvar::SpinCondvar;
+pub use event::Event;
+#[cfg(feature = "os-wait")]
+pub use futex::{FutexGuard, FutexLock};
+pub use guard::{Guard, MappedGuard};
+pub use guarded::GuardedBy;
+pub use hierarchy::{HierarchicalGuard, HierarchicalSpinLock};
+#[cfg(feature = "std")]
+pub use hybrid::{HybridGuard, HybridMutex};
+#[cfg(feature = "critical-section")]
+pub use irq::{CriticalSection, CriticalSectionSpinLock};
+pub use irq::{Interrupts, IrqGuard, IrqSpinLock};
+#[cfg(feature = "std")]
+pub use keyed::{KeyGuard, KeyedLock};
+pub use latch::Latch;
+pub use lazy::Lazy;
+pub use lock::SpinLock;
+#[cfg(feature = "std")]
+pub use map::SpinMap;
+pub use mcs::{McsGuard, McsLock, McsNode};
+pub use multi::{lock_all, lock_both};
+pub use once::SpinOnce;
+pub use ordered::{
+    Level, Level1, Level2, Level3, Level4, LockToken, LowerThan, OrderedSpinLock, Unlocked,
+};
+#[cfg(feature = "alloc")]
+pub use owned::{OwnedGuard, OwnedMappedGuard};
+pub use padded::CachePadded;
+pub use phase_fair::{PhaseFairReadGuard, PhaseFairRwLock, PhaseFairWriteGuard};
+#[cfg(feature = "poison")]
+pub use poison::{PoisonGuard, PoisonSpinLock};
+pub use priority::{InheritanceHook, PriorityGuard, PriorityLock, PriorityNode};
+pub use queue::SpinQueue;
+pub use raw::RawSpinLock;
+#[cfg(feature = "alloc")]
+pub use rcu::RcuCell;
+#[cfg(feature = "std")]
+pub use reentrant::{ReentrantGuard, ReentrantSpinLock};
+#[cfg(feature = "std")]
+pub use relax::{SpinThenYield, Yield};
+pub use relax::{Relax, Spin};
+pub use rwlock::{
+    RawRwSpinLock, ReadGuard, RwPolicy, RwSpinLock, UpgradableReadGuard, WriteGuard,
+};
+pub use semaphore::{Permit, SpinSemaphore};
+pub use seqcount::SeqCount;
+pub use seqlock::{SeqLock, SeqLockGuard};
+pub use sharded::ShardedSpinLock;
+#[cfg(feature = "stats")]
+pub use stats::LockStats;
+pub use ticket::{TicketGuard, TicketLock};
+pub use timeout::{timeout, Timeout};
+pub use wait_group::WaitGroup;
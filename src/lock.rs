@@ -0,0 +1,391 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::{self, MaybeUninit};
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::ptr;
+use core::sync::atomic::AtomicBool;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+use crate::{Backoff, Guard, MappedGuard, RawSpinLock, Relax};
+
+/// `R` picks how waiting threads spin, see `Relax`.
+pub struct SpinLock<T: ?Sized, R = Backoff> {
+    pub(crate) raw: RawSpinLock<R>,
+    pub(crate) value: UnsafeCell<T>,
+}
+
+/*
+ * UnsafeCell does not implement sync, making the type no longer sharable between threads.
+ * To fix, we need to promise the type is actually safe by impl'ing Sync for every type that
+ * is Send.
+ */
+unsafe impl<T: ?Sized, R> Sync for SpinLock<T, R> where T: Send {}
+
+// Like std's Mutex, so a lock can be used from catch_unwind without
+// AssertUnwindSafe. Unlike it, nobody is told when a holder panicked halfway
+// through an update, that's what `PoisonSpinLock` is for.
+impl<T: ?Sized, R> UnwindSafe for SpinLock<T, R> {}
+impl<T: ?Sized, R> RefUnwindSafe for SpinLock<T, R> {}
+
+impl<T> SpinLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self::with_relax(value)
+    }
+
+    /// A lock with a name for diagnostics, see `RawSpinLock::new_named`.
+    pub const fn new_named(name: &'static str, value: T) -> Self {
+        Self {
+            raw: RawSpinLock::new_named(name),
+            value: UnsafeCell::new(value),
+        }
+    }
+}
+
+impl<T, R: Relax> SpinLock<T, R> {
+    /// `new` for a non-default relax strategy, e.g.
+    /// `SpinLock::<_, Yield>::with_relax(value)`.
+    pub const fn with_relax(value: T) -> Self {
+        Self {
+            raw: RawSpinLock::with_relax(),
+            value: UnsafeCell::new(value),
+        }
+    }
+}
+
+impl<T: ?Sized, R: Relax> SpinLock<T, R> {
+    #[inline]
+    pub fn lock(&self) -> Guard<'_, T, R> {
+        self.raw.lock();
+        Guard {
+            lock: self,
+            marker: PhantomData,
+        }
+    }
+
+    // Single attempt, never spins. None means someone else holds the lock.
+    #[inline]
+    pub fn try_lock(&self) -> Option<Guard<'_, T, R>> {
+        self.raw.try_lock().then(|| Guard {
+            lock: self,
+            marker: PhantomData,
+        })
+    }
+
+    /// Runs `f` with the lock held and unlocks straight after, so the guard
+    /// can't end up held across an `.await` or a long computation by mistake.
+    #[inline]
+    pub fn with<U, F: FnOnce(&mut T) -> U>(&self, f: F) -> U {
+        f(&mut self.lock())
+    }
+
+    /// `with` that runs `f` only if the lock is free right now.
+    #[inline]
+    pub fn try_with<U, F: FnOnce(&mut T) -> U>(&self, f: F) -> Option<U> {
+        self.try_lock().map(|mut guard| f(&mut guard))
+    }
+
+    /// Changes the value in place under the lock, the closest thing to an
+    /// atomic read-modify-write for arbitrary data.
+    #[inline]
+    pub fn update<F: FnOnce(&mut T)>(&self, f: F) {
+        f(&mut self.lock())
+    }
+
+    /// `lock` that gives up once `cancel` is set, see
+    /// `RawSpinLock::lock_with_cancel`. None means it was cancelled.
+    pub fn lock_with_cancel(&self, cancel: &AtomicBool) -> Option<Guard<'_, T, R>> {
+        self.raw.lock_with_cancel(cancel).then(|| Guard {
+            lock: self,
+            marker: PhantomData,
+        })
+    }
+
+    /// Whether some guard currently holds the lock. Only a snapshot, the
+    /// answer may be stale by the time the caller looks at it, so this is for
+    /// diagnostics and assertions rather than synchronization.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.raw.is_locked()
+    }
+
+    /// Releases the lock without a guard, for recovery and FFI code that
+    /// had to `mem::forget` the guard, e.g. to hold the lock across a C
+    /// callback.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held, and the guard that took it must be gone
+    /// without having unlocked it: leaked with `mem::forget` or
+    /// `Guard::leak`, with no reference it handed out still in use.
+    /// Otherwise two threads can end up holding the lock at once.
+    #[inline]
+    pub unsafe fn force_unlock(&self) {
+        self.raw.unlock()
+    }
+
+    /// The address of the protected value, without locking, for FFI and
+    /// intrusive structures that need to know where it lives.
+    ///
+    /// Getting the pointer is safe, using it is not: reading or writing
+    /// through it is only sound while holding the lock (e.g. through a
+    /// forgotten guard, see `force_unlock`), and it must not be used to
+    /// create a reference that outlives that.
+    #[inline]
+    pub fn data_ptr(&self) -> *mut T {
+        self.value.get()
+    }
+
+    /// Contention counters since the lock was created, with the `stats`
+    /// feature.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::LockStats {
+        self.raw.stats()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: ?Sized, R: Relax> SpinLock<T, R> {
+    /// `lock` that gives up after `timeout`, for callers that would rather
+    /// fail than wait forever on a stuck holder. None means it timed out.
+    pub fn try_lock_for(&self, timeout: Duration) -> Option<Guard<'_, T, R>> {
+        self.raw.try_lock_for(timeout).then(|| Guard {
+            lock: self,
+            marker: PhantomData,
+        })
+    }
+
+    /// `try_lock_for` with an absolute deadline.
+    pub fn try_lock_until(&self, deadline: Instant) -> Option<Guard<'_, T, R>> {
+        self.raw.try_lock_until(deadline).then(|| Guard {
+            lock: self,
+            marker: PhantomData,
+        })
+    }
+
+    /// `try_with` that waits up to `timeout` for the lock. The guard never
+    /// leaves the call, so there's no way to hold it past `f`.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let queue = spinlock::SpinLock::new(vec![1]);
+    /// let popped = queue.try_with_for(Duration::from_micros(50), |q| q.pop());
+    /// assert_eq!(popped, Some(Some(1)));
+    ///
+    /// let _held = queue.lock();
+    /// assert_eq!(queue.try_with_for(Duration::ZERO, |q| q.pop()), None);
+    /// ```
+    #[inline]
+    pub fn try_with_for<U, F: FnOnce(&mut T) -> U>(&self, timeout: Duration, f: F) -> Option<U> {
+        self.try_lock_for(timeout).map(|mut guard| f(&mut guard))
+    }
+
+    /// `try_lock_for` whose error says which thread held the lock and where
+    /// it took it, with the `diagnostics` feature.
+    #[cfg(feature = "diagnostics")]
+    pub fn lock_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<Guard<'_, T, R>, crate::diagnostics::TimedOut> {
+        self.raw.lock_timeout(timeout).map(|()| Guard {
+            lock: self,
+            marker: PhantomData,
+        })
+    }
+
+    /// Who holds the lock right now, see `RawSpinLock::owner`.
+    #[cfg(feature = "diagnostics")]
+    pub fn owner(&self) -> Option<crate::diagnostics::Owner> {
+        self.raw.owner()
+    }
+}
+
+// Shortcuts for working with the whole value, each holding the lock only for
+// the one operation.
+impl<T, R: Relax> SpinLock<T, R> {
+    /// A copy of the value, taken under the lock.
+    pub fn get_cloned(&self) -> T
+    where
+        T: Clone,
+    {
+        self.lock().clone()
+    }
+
+    /// A copy of the value, read without taking the lock as long as nobody
+    /// holds it, so readers of small `Copy` data don't contend with each
+    /// other. Falls back to locking if a holder gets in the way.
+    #[inline]
+    pub fn load(&self) -> T
+    where
+        T: Copy,
+    {
+        let version = self.raw.version();
+        if version & 1 == 0 {
+            // safety: the copy may race with a holder and come out torn,
+            // which is why it stays a MaybeUninit until validated
+            let value = unsafe { ptr::read_volatile(self.value.get() as *const MaybeUninit<T>) };
+            if self.raw.validate(version) {
+                // safety: nobody held the lock while we copied the value
+                return unsafe { value.assume_init() };
+            }
+        }
+        *self.lock()
+    }
+
+    /// Overwrites the value. The old one is dropped after unlocking, so a
+    /// slow `Drop` doesn't hold up other threads.
+    pub fn set(&self, value: T) {
+        drop(self.replace(value));
+    }
+
+    /// Puts `value` in and returns the old value.
+    pub fn replace(&self, value: T) -> T {
+        mem::replace(&mut *self.lock(), value)
+    }
+
+    /// Takes the value out, leaving `T::default()` behind.
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        mem::take(&mut *self.lock())
+    }
+
+    /// `update` that also returns a copy of the new value, taken before
+    /// anyone else can change it again.
+    pub fn update_and_fetch<F: FnOnce(&mut T)>(&self, f: F) -> T
+    where
+        T: Clone,
+    {
+        let mut guard = self.lock();
+        f(&mut guard);
+        guard.clone()
+    }
+
+    /// Runs `f` on the value under the lock, all or nothing: if `f` returns
+    /// an error or panics, the value is put back the way it was before
+    /// anyone else gets to see it.
+    ///
+    /// ```
+    /// let config = spinlock::SpinLock::new(vec![1, 2]);
+    /// let result: Result<(), &str> = config.lock_with_rollback(|v| {
+    ///     v.push(3);
+    ///     Err("invalid")
+    /// });
+    /// assert!(result.is_err());
+    /// assert_eq!(*config.lock(), [1, 2]);
+    /// ```
+    pub fn lock_with_rollback<U, E, F>(&self, f: F) -> Result<U, E>
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> Result<U, E>,
+    {
+        // puts the snapshot back when dropped, unless disarmed
+        struct Rollback<'a, T> {
+            value: &'a mut T,
+            snapshot: Option<T>,
+        }
+        impl<T> Drop for Rollback<'_, T> {
+            fn drop(&mut self) {
+                if let Some(snapshot) = self.snapshot.take() {
+                    *self.value = snapshot;
+                }
+            }
+        }
+
+        let mut guard = self.lock();
+        let snapshot = Some(T::clone(&guard));
+        let mut rollback = Rollback {
+            value: &mut *guard,
+            snapshot,
+        };
+        let result = f(rollback.value);
+        if result.is_ok() {
+            rollback.snapshot = None;
+        }
+        result
+    }
+}
+
+impl<T, R: Relax> SpinLock<Option<T>, R> {
+    /// The value, set to `f()` first if it's still None, with the lock held.
+    /// Only one caller ever runs `f`; if it panics the value stays None.
+    ///
+    /// There's no unlocked fast path: the guard it returns holds the lock
+    /// anyway, and anyone holding the lock can change the value or take it
+    /// out again, so an unlocked "already set" check would prove nothing.
+    /// For something set once and only read after that, use `SpinOnce`,
+    /// whose `get_or_init` is the double-checked version and hands out a
+    /// plain `&T` without locking once the value is there.
+    ///
+    /// ```
+    /// let cache = spinlock::SpinLock::new(None);
+    /// assert_eq!(*cache.get_or_init_with(|| 1), 1);
+    /// assert_eq!(*cache.get_or_init_with(|| 2), 1);
+    ///
+    /// let config = spinlock::SpinOnce::new();
+    /// assert_eq!(*config.get_or_init(|| 1), 1);
+    /// assert_eq!(*config.get_or_init(|| 2), 1);
+    /// ```
+    pub fn get_or_init_with<F: FnOnce() -> T>(&self, f: F) -> MappedGuard<'_, T, R> {
+        Guard::map(self.lock(), |value| value.get_or_insert_with(f))
+    }
+}
+
+// Exclusive access to the lock itself already rules out any other holder,
+// so none of these need to touch the lock state.
+impl<T, R> SpinLock<T, R> {
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized, R> SpinLock<T, R> {
+    /// Mutable access without locking, since `&mut self` rules out any
+    /// holder.
+    ///
+    /// There's no `from_mut` going the other way, like `Cell::from_mut`: the
+    /// lock word has to live somewhere, and a `&mut T` has no room for it.
+    /// To share data owned elsewhere for a while, lock the borrow instead:
+    ///
+    /// ```
+    /// let mut hits = vec![0; 4];
+    /// let shared = spinlock::SpinLock::new(&mut hits);
+    /// std::thread::scope(|s| {
+    ///     for i in 0..4 {
+    ///         let shared = &shared;
+    ///         s.spawn(move || shared.lock()[i] += 1);
+    ///     }
+    /// });
+    /// assert_eq!(hits, [1; 4]);
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T: Default, R: Relax> Default for SpinLock<T, R> {
+    fn default() -> Self {
+        Self::with_relax(T::default())
+    }
+}
+
+impl<T, R: Relax> From<T> for SpinLock<T, R> {
+    fn from(value: T) -> Self {
+        Self::with_relax(value)
+    }
+}
+
+// Never blocks: a held lock prints as `<locked>` like std's Mutex does.
+impl<T: ?Sized + fmt::Debug, R: Relax> fmt::Debug for SpinLock<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("SpinLock");
+        match self.try_lock() {
+            Some(guard) => d.field("data", &&*guard),
+            None => d.field("data", &format_args!("<locked>")),
+        };
+        d.finish_non_exhaustive()
+    }
+}
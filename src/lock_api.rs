@@ -0,0 +1,192 @@
+//! `lock_api` integration: `RawSpinLock` as a `lock_api::RawMutex` and
+//! `RawRwSpinLock` as a `lock_api::RawRwLock`, plus the usual type aliases so
+//! `Mutex<T>` and `RwLock<T>` come with mapped guards and the rest of the
+//! `lock_api` surface.
+
+use crate::{Backoff, RawRwSpinLock, RawSpinLock, Relax};
+
+pub type Mutex<T, R = Backoff> = ::lock_api::Mutex<RawSpinLock<R>, T>;
+pub type MutexGuard<'a, T, R = Backoff> = ::lock_api::MutexGuard<'a, RawSpinLock<R>, T>;
+pub type MappedMutexGuard<'a, T, R = Backoff> =
+    ::lock_api::MappedMutexGuard<'a, RawSpinLock<R>, T>;
+
+pub type RwLock<T> = ::lock_api::RwLock<RawRwSpinLock, T>;
+pub type RwLockReadGuard<'a, T> = ::lock_api::RwLockReadGuard<'a, RawRwSpinLock, T>;
+pub type RwLockWriteGuard<'a, T> = ::lock_api::RwLockWriteGuard<'a, RawRwSpinLock, T>;
+pub type RwLockUpgradableReadGuard<'a, T> =
+    ::lock_api::RwLockUpgradableReadGuard<'a, RawRwSpinLock, T>;
+pub type MappedRwLockReadGuard<'a, T> = ::lock_api::MappedRwLockReadGuard<'a, RawRwSpinLock, T>;
+pub type MappedRwLockWriteGuard<'a, T> = ::lock_api::MappedRwLockWriteGuard<'a, RawRwSpinLock, T>;
+
+unsafe impl<R: Relax> ::lock_api::RawMutex for RawSpinLock<R> {
+    const INIT: Self = RawSpinLock::with_relax();
+
+    // nothing about a spinlock ties it to the thread that locked it
+    type GuardMarker = ::lock_api::GuardSend;
+
+    #[inline]
+    fn lock(&self) {
+        RawSpinLock::lock(self)
+    }
+
+    #[inline]
+    fn try_lock(&self) -> bool {
+        RawSpinLock::try_lock(self)
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        RawSpinLock::unlock(self)
+    }
+
+    #[inline]
+    fn is_locked(&self) -> bool {
+        RawSpinLock::is_locked(self)
+    }
+}
+
+#[cfg(feature = "std")]
+unsafe impl<R: Relax> ::lock_api::RawMutexTimed for RawSpinLock<R> {
+    type Duration = std::time::Duration;
+    type Instant = std::time::Instant;
+
+    #[inline]
+    fn try_lock_for(&self, timeout: Self::Duration) -> bool {
+        RawSpinLock::try_lock_for(self, timeout)
+    }
+
+    #[inline]
+    fn try_lock_until(&self, deadline: Self::Instant) -> bool {
+        RawSpinLock::try_lock_until(self, deadline)
+    }
+}
+
+unsafe impl ::lock_api::RawRwLock for RawRwSpinLock {
+    const INIT: Self = RawRwSpinLock::new();
+
+    type GuardMarker = ::lock_api::GuardSend;
+
+    #[inline]
+    fn lock_shared(&self) {
+        self.read()
+    }
+
+    #[inline]
+    fn try_lock_shared(&self) -> bool {
+        self.try_read()
+    }
+
+    #[inline]
+    unsafe fn unlock_shared(&self) {
+        self.unlock_read()
+    }
+
+    #[inline]
+    fn lock_exclusive(&self) {
+        self.write()
+    }
+
+    #[inline]
+    fn try_lock_exclusive(&self) -> bool {
+        self.try_write()
+    }
+
+    #[inline]
+    unsafe fn unlock_exclusive(&self) {
+        self.unlock_write()
+    }
+
+    #[inline]
+    fn is_locked(&self) -> bool {
+        RawRwSpinLock::is_locked(self)
+    }
+
+    #[inline]
+    fn is_locked_exclusive(&self) -> bool {
+        self.is_write_locked()
+    }
+}
+
+unsafe impl ::lock_api::RawRwLockRecursive for RawRwSpinLock {
+    #[inline]
+    fn lock_shared_recursive(&self) {
+        self.read_recursive()
+    }
+
+    #[inline]
+    fn try_lock_shared_recursive(&self) -> bool {
+        self.try_read_recursive()
+    }
+}
+
+#[cfg(feature = "std")]
+unsafe impl ::lock_api::RawRwLockTimed for RawRwSpinLock {
+    type Duration = std::time::Duration;
+    type Instant = std::time::Instant;
+
+    #[inline]
+    fn try_lock_shared_for(&self, timeout: Self::Duration) -> bool {
+        match std::time::Instant::now().checked_add(timeout) {
+            Some(deadline) => self.try_read_until(deadline),
+            None => {
+                self.read();
+                true
+            }
+        }
+    }
+
+    #[inline]
+    fn try_lock_shared_until(&self, deadline: Self::Instant) -> bool {
+        self.try_read_until(deadline)
+    }
+
+    #[inline]
+    fn try_lock_exclusive_for(&self, timeout: Self::Duration) -> bool {
+        match std::time::Instant::now().checked_add(timeout) {
+            Some(deadline) => self.try_write_until(deadline),
+            None => {
+                self.write();
+                true
+            }
+        }
+    }
+
+    #[inline]
+    fn try_lock_exclusive_until(&self, deadline: Self::Instant) -> bool {
+        self.try_write_until(deadline)
+    }
+}
+
+unsafe impl ::lock_api::RawRwLockDowngrade for RawRwSpinLock {
+    #[inline]
+    unsafe fn downgrade(&self) {
+        RawRwSpinLock::downgrade(self)
+    }
+}
+
+unsafe impl ::lock_api::RawRwLockUpgrade for RawRwSpinLock {
+    #[inline]
+    fn lock_upgradable(&self) {
+        self.upgradable()
+    }
+
+    #[inline]
+    fn try_lock_upgradable(&self) -> bool {
+        self.try_upgradable()
+    }
+
+    #[inline]
+    unsafe fn unlock_upgradable(&self) {
+        RawRwSpinLock::unlock_upgradable(self)
+    }
+
+    #[inline]
+    unsafe fn upgrade(&self) {
+        RawRwSpinLock::upgrade(self)
+    }
+
+    #[inline]
+    unsafe fn try_upgrade(&self) -> bool {
+        RawRwSpinLock::try_upgrade(self)
+    }
+}
@@ -0,0 +1,93 @@
+use core::borrow::Borrow;
+use core::hash::Hash;
+use std::collections::HashMap;
+
+use crate::ShardedSpinLock;
+
+/// A concurrent hash map made of `N` spinlocked `HashMap`s. Every operation
+/// locks only the shard its key hashes to, so threads working on different
+/// keys rarely meet. Values are only reachable inside closures, which keeps
+/// the shard locked for no longer than the closure runs.
+pub struct SpinMap<K, V, const N: usize = 16> {
+    shards: ShardedSpinLock<HashMap<K, V>, N>,
+}
+
+impl<K: Hash + Eq, V, const N: usize> SpinMap<K, V, N> {
+    pub fn new() -> Self {
+        Self {
+            shards: ShardedSpinLock::from_fn(|_| HashMap::new()),
+        }
+    }
+
+    /// Returns the old value for `key`, if any.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.shards.get(&key).lock().insert(key, value)
+    }
+
+    /// Runs `f` on the value for `key` with its shard locked.
+    pub fn get_with<Q, U, F>(&self, key: &Q, f: F) -> Option<U>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnOnce(&V) -> U,
+    {
+        self.shards.get(key).lock().get(key).map(f)
+    }
+
+    /// `get_with` with mutable access to the value.
+    pub fn get_mut_with<Q, U, F>(&self, key: &Q, f: F) -> Option<U>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnOnce(&mut V) -> U,
+    {
+        self.shards.get(key).lock().get_mut(key).map(f)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shards.get(key).lock().contains_key(key)
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shards.get(key).lock().remove(key)
+    }
+
+    /// Visits every entry, one shard at a time. Entries inserted or removed
+    /// concurrently may or may not be seen. `f` must not call back into the
+    /// map for a key in the shard being visited, that would deadlock.
+    pub fn for_each<F: FnMut(&K, &V)>(&self, mut f: F) {
+        for shard in self.shards.iter_shards() {
+            for (key, value) in shard.lock().iter() {
+                f(key, value);
+            }
+        }
+    }
+
+    /// Summed shard by shard, so only exact while nobody else is writing.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter_shards()
+            .map(|shard| shard.lock().len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards
+            .iter_shards()
+            .all(|shard| shard.lock().is_empty())
+    }
+}
+
+impl<K: Hash + Eq, V, const N: usize> Default for SpinMap<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
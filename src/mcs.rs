@@ -0,0 +1,201 @@
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+
+use crate::sync::{AcqRel, Acquire, AtomicBool, AtomicPtr, Relaxed, Release};
+use crate::Backoff;
+
+/// Queue entry for one waiter on an `McsLock`. Usually lives on the stack of
+/// the thread taking the lock; it's borrowed for as long as the guard lives,
+/// so it can't move or be reused while it's linked into the queue.
+pub struct McsNode {
+    next: AtomicPtr<McsNode>,
+    waiting: AtomicBool,
+}
+
+impl McsNode {
+    pub const fn new() -> Self {
+        Self {
+            next: AtomicPtr::new(ptr::null_mut()),
+            waiting: AtomicBool::new(false),
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn wait_for_successor(&self) -> *mut McsNode {
+        let mut backoff = Backoff::new();
+        loop {
+            let next = self.next.load(Acquire);
+            if !next.is_null() {
+                return next;
+            }
+            backoff.relax();
+        }
+    }
+}
+
+impl Default for McsNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// MCS queue lock: waiters form a linked list of `McsNode`s and each one
+/// spins on a flag in its own node, so a release only touches the cache line
+/// of the next waiter instead of every waiting core.
+pub struct McsLock<T> {
+    pub(crate) tail: AtomicPtr<McsNode>,
+    pub(crate) value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for McsLock<T> where T: Send {}
+
+impl<T> McsLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            tail: AtomicPtr::new(ptr::null_mut()),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline]
+    pub fn lock<'a>(&'a self, node: &'a mut McsNode) -> McsGuard<'a, T> {
+        // only shared references from here on, the successor writes our `next`
+        let node = &*node;
+        self.acquire(node);
+        McsGuard {
+            lock: self,
+            node,
+            marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn acquire(&self, node: &McsNode) {
+        node.next.store(ptr::null_mut(), Relaxed);
+        node.waiting.store(true, Relaxed);
+        let pred = self.tail.swap(node as *const _ as *mut _, AcqRel);
+        if !pred.is_null() {
+            self.lock_slow(pred, node);
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn lock_slow(&self, pred: *mut McsNode, node: &McsNode) {
+        // safety: a node stays alive until its holder has handed the lock on,
+        // and that can't happen before we've linked ourselves in here
+        unsafe { (*pred).next.store(node as *const _ as *mut _, Release) };
+        let mut backoff = Backoff::new();
+        while node.waiting.load(Acquire) {
+            backoff.relax();
+        }
+    }
+
+    #[inline]
+    pub fn try_lock<'a>(&'a self, node: &'a mut McsNode) -> Option<McsGuard<'a, T>> {
+        let node = &*node;
+        node.next.store(ptr::null_mut(), Relaxed);
+        self.tail
+            .compare_exchange(ptr::null_mut(), node as *const _ as *mut _, Acquire, Relaxed)
+            .ok()
+            .map(|_| McsGuard {
+                lock: self,
+                node,
+                marker: PhantomData,
+            })
+    }
+}
+
+/// Like `Guard`, can be sent to another thread, which then hands the lock
+/// on: the node outlives the guard wherever it goes, and is only touched
+/// through atomics.
+///
+/// ```
+/// let lock = spinlock::McsLock::new(0);
+/// let mut node = spinlock::McsNode::new();
+/// std::thread::scope(|s| {
+///     let mut guard = lock.lock(&mut node);
+///     s.spawn(move || *guard += 1);
+/// });
+/// ```
+pub struct McsGuard<'a, T> {
+    pub(crate) lock: &'a McsLock<T>,
+    pub(crate) node: &'a McsNode,
+    // Send and Sync are implemented below rather than following `lock`
+    pub(crate) marker: PhantomData<*const ()>,
+}
+
+unsafe impl<T: Send> Send for McsGuard<'_, T> {}
+unsafe impl<T: Sync> Sync for McsGuard<'_, T> {}
+
+impl<T> McsGuard<'_, T> {
+    /// Unlocks, handing the lock straight to the next queued node. That's
+    /// what dropping the guard does too: the lock only becomes free for
+    /// anyone to take when the queue is empty, so no thread can barge in
+    /// ahead of a waiter.
+    pub fn unlock_fair(self) {
+        drop(self)
+    }
+
+    /// Hands the lock to the next queued waiter, if any, and queues up again
+    /// behind everyone already waiting, so a long loop holding the lock can
+    /// let the others in. An associated function so it doesn't shadow a
+    /// `bump` on `T`.
+    pub fn bump(this: &mut Self) {
+        // safety: the guard is the proof we hold the lock, and it keeps not
+        // being used until we're back at the head of the queue. Once handed
+        // on, nobody touches the node anymore, so it can go straight back in.
+        unsafe { this.lock.release(this.node) };
+        this.lock.acquire(this.node);
+    }
+}
+
+impl<T> McsLock<T> {
+    // Safety: `node` must be the node at the head of the queue, i.e. the one
+    // holding the lock
+    #[inline]
+    unsafe fn release(&self, node: &McsNode) {
+        let mut next = node.next.load(Acquire);
+        if next.is_null() {
+            let me = node as *const _ as *mut _;
+            if self
+                .tail
+                .compare_exchange(me, ptr::null_mut(), Release, Relaxed)
+                .is_ok()
+            {
+                return;
+            }
+            // someone swapped themselves in as tail but hasn't linked to us yet
+            next = node.wait_for_successor();
+        }
+        // safety: the successor is spinning on its node and won't free it
+        // until it sees this store
+        (*next).waiting.store(false, Release);
+    }
+}
+
+impl<T> Deref for McsGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: only the node at the head of the queue has a guard
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for McsGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: only the node at the head of the queue has a guard
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for McsGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // safety: the guard is the proof our node holds the lock
+        unsafe { self.lock.release(self.node) }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{Guard, Relax, SpinLock};
+
+/// Locks two spinlocks, always in the same order (by address) whichever way
+/// round they're passed, so two threads calling `lock_both(&a, &b)` and
+/// `lock_both(&b, &a)` can't deadlock each other. That only holds among
+/// callers of `lock_both` and `lock_all`; code locking the same pair by hand
+/// can still deadlock with them.
+///
+/// Panics if both are the same lock, which could never be locked twice.
+pub fn lock_both<'a, A: ?Sized, B: ?Sized, RA: Relax, RB: Relax>(
+    a: &'a SpinLock<A, RA>,
+    b: &'a SpinLock<B, RB>,
+) -> (Guard<'a, A, RA>, Guard<'a, B, RB>) {
+    let (addr_a, addr_b) = (addr(a), addr(b));
+    assert_ne!(addr_a, addr_b, "lock_both called with the same lock twice");
+    if addr_a < addr_b {
+        let a = a.lock();
+        (a, b.lock())
+    } else {
+        let b = b.lock();
+        (a.lock(), b)
+    }
+}
+
+/// `lock_both` for any number of locks of the same type. The guards come
+/// back in the order the locks were passed in.
+///
+/// Panics if a lock appears more than once.
+pub fn lock_all<'a, T: ?Sized, R: Relax, const N: usize>(
+    locks: [&'a SpinLock<T, R>; N],
+) -> [Guard<'a, T, R>; N] {
+    let mut order: [usize; N] = core::array::from_fn(|i| i);
+    order.sort_unstable_by_key(|&i| addr(locks[i]));
+    for pair in order.windows(2) {
+        assert_ne!(
+            addr(locks[pair[0]]),
+            addr(locks[pair[1]]),
+            "lock_all called with the same lock twice"
+        );
+    }
+    // if a lock panics, the guards taken so far still unlock
+    let mut guards: [Option<Guard<'a, T, R>>; N] = core::array::from_fn(|_| None);
+    for i in order {
+        guards[i] = Some(locks[i].lock());
+    }
+    guards.map(|guard| guard.unwrap())
+}
+
+// The lock state's address rather than the SpinLock's: a SpinLock nested
+// directly in another may share its starting address.
+fn addr<T: ?Sized, R>(lock: &SpinLock<T, R>) -> usize {
+    &lock.raw as *const _ as usize
+}
@@ -0,0 +1,53 @@
+//! `mutex-trait` support, with the `mutex-trait` feature: a shared reference
+//! to a lock is a `mutex_trait::Mutex`, the trait embedded drivers take to
+//! share a bus (SPI, I2C) between them without being tied to an RTOS or a
+//! particular lock. `IrqSpinLock` is the one to pick when an interrupt
+//! handler uses the bus too.
+//!
+//! ```
+//! # #[cfg(feature = "mutex-trait")] {
+//! use mutex_trait::Mutex;
+//! use spinlock::SpinLock;
+//!
+//! // a driver that only knows it gets exclusive access to the bus
+//! fn send(mut bus: impl Mutex<Data = Vec<u8>>, byte: u8) {
+//!     bus.lock(|bus| bus.push(byte));
+//! }
+//!
+//! let bus = SpinLock::new(Vec::new());
+//! send(&bus, 1);
+//! send(&bus, 2);
+//! assert_eq!(*bus.lock(), [1, 2]);
+//! # }
+//! ```
+
+use ::mutex_trait::Mutex;
+
+use crate::{Interrupts, IrqSpinLock, Relax, SpinLock, TicketLock};
+
+impl<T, R: Relax> Mutex for &SpinLock<T, R> {
+    type Data = T;
+
+    #[inline]
+    fn lock<U>(&mut self, f: impl FnOnce(&mut T) -> U) -> U {
+        f(&mut SpinLock::lock(self))
+    }
+}
+
+impl<T> Mutex for &TicketLock<T> {
+    type Data = T;
+
+    #[inline]
+    fn lock<U>(&mut self, f: impl FnOnce(&mut T) -> U) -> U {
+        f(&mut TicketLock::lock(self))
+    }
+}
+
+impl<T, I: Interrupts> Mutex for &IrqSpinLock<T, I> {
+    type Data = T;
+
+    #[inline]
+    fn lock<U>(&mut self, f: impl FnOnce(&mut T) -> U) -> U {
+        f(&mut IrqSpinLock::lock(self))
+    }
+}
@@ -0,0 +1,162 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::sync::atomic::AtomicU8;
+
+use crate::sync::{Acquire, Release};
+use crate::Backoff;
+
+const INCOMPLETE: u8 = 0;
+const RUNNING: u8 = 1;
+const COMPLETE: u8 = 2;
+
+/// One-time initialization, `no_std` friendly. Works as a plain `Once` with
+/// `SpinOnce<()>` and `call_once`, or as a `OnceLock` with `get_or_init`.
+pub struct SpinOnce<T = ()> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// Every thread that calls get sees the same &T, and whichever thread runs the
+// initializer may not be the one that drops the value.
+unsafe impl<T: Send + Sync> Sync for SpinOnce<T> {}
+unsafe impl<T: Send> Send for SpinOnce<T> {}
+
+impl<T> SpinOnce<T> {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(INCOMPLETE),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Returns the value, running `f` to make it if nobody has yet. If
+    /// another thread is already running its initializer, waits for that one
+    /// instead. A panicking `f` leaves the once uninitialized, so the next
+    /// caller gets to try again.
+    #[inline]
+    pub fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T {
+        match self.get() {
+            Some(value) => value,
+            None => self.init_slow(f),
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn init_slow<F: FnOnce() -> T>(&self, f: F) -> &T {
+        let mut backoff = Backoff::with_jitter();
+        loop {
+            match self
+                .state
+                .compare_exchange_weak(INCOMPLETE, RUNNING, Acquire, Acquire)
+            {
+                Ok(_) => break,
+                Err(COMPLETE) => {
+                    // safety: COMPLETE is only stored after the value is written
+                    return unsafe { self.get_unchecked() };
+                }
+                Err(_) => backoff.relax(),
+            }
+        }
+
+        // puts the state back if f panics, so waiters don't spin forever
+        struct Reset<'a>(&'a AtomicU8);
+        impl Drop for Reset<'_> {
+            fn drop(&mut self) {
+                self.0.store(INCOMPLETE, Release);
+            }
+        }
+
+        let reset = Reset(&self.state);
+        let value = f();
+        core::mem::forget(reset);
+        // safety: the RUNNING state gives us the only access to the slot
+        unsafe { (*self.value.get()).write(value) };
+        self.state.store(COMPLETE, Release);
+        // safety: just written
+        unsafe { self.get_unchecked() }
+    }
+
+    /// The value, if initialization has finished. Never waits.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        // safety: COMPLETE is only stored after the value is written
+        self.is_completed().then(|| unsafe { self.get_unchecked() })
+    }
+
+    #[inline]
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Acquire) == COMPLETE
+    }
+
+    // Exclusive access rules out an initializer running on another thread.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.state.get_mut() == COMPLETE {
+            // safety: COMPLETE means the value is written
+            Some(unsafe { self.value.get_mut().assume_init_mut() })
+        } else {
+            None
+        }
+    }
+
+    pub fn into_inner(mut self) -> Option<T> {
+        let complete = *self.state.get_mut() == COMPLETE;
+        // stop Drop from dropping the value we're moving out
+        *self.state.get_mut() = INCOMPLETE;
+        // safety: COMPLETE means the value is written, and it's read only once
+        complete.then(|| unsafe { self.value.get_mut().assume_init_read() })
+    }
+
+    // Safety: the state must be COMPLETE
+    #[inline]
+    unsafe fn get_unchecked(&self) -> &T {
+        (*self.value.get()).assume_init_ref()
+    }
+}
+
+impl SpinOnce<()> {
+    /// Runs `f` if no call to `call_once` has completed yet. Every caller
+    /// returns only once `f` has run to completion on some thread.
+    #[inline]
+    pub fn call_once<F: FnOnce()>(&self, f: F) {
+        self.get_or_init(f);
+    }
+}
+
+impl<T> Default for SpinOnce<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<T> for SpinOnce<T> {
+    fn from(value: T) -> Self {
+        Self {
+            state: AtomicU8::new(COMPLETE),
+            value: UnsafeCell::new(MaybeUninit::new(value)),
+        }
+    }
+}
+
+// Never blocks: prints `<uninit>` while nobody has finished initializing.
+impl<T: fmt::Debug> fmt::Debug for SpinOnce<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_tuple("SpinOnce");
+        match self.get() {
+            Some(value) => d.field(value),
+            None => d.field(&format_args!("<uninit>")),
+        };
+        d.finish()
+    }
+}
+
+impl<T> Drop for SpinOnce<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == COMPLETE {
+            // safety: COMPLETE means the value is written, and this is the
+            // last anyone sees of it
+            unsafe { self.value.get_mut().assume_init_drop() }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use core::marker::PhantomData;
+
+use crate::{Guard, SpinLock};
+
+/// A level in a compile-time lock hierarchy, see `OrderedSpinLock`.
+pub trait Level {}
+
+/// `Self` may be held while locking something at level `L`.
+pub trait LowerThan<L: Level>: Level {}
+
+/// The level of a thread holding no ordered locks.
+pub struct Unlocked;
+
+impl Level for Unlocked {}
+
+macro_rules! levels {
+    ($($level:ident),* $(,)?) => {
+        $(
+            pub struct $level;
+            impl Level for $level {}
+            impl LowerThan<$level> for Unlocked {}
+        )*
+        levels!(@order $($level),*);
+    };
+    (@order $first:ident $(, $rest:ident)*) => {
+        $(impl LowerThan<$rest> for $first {})*
+        levels!(@order $($rest),*);
+    };
+    (@order) => {};
+}
+
+levels!(Level1, Level2, Level3, Level4);
+
+/// Proof of the highest level the current thread holds. Locking an
+/// `OrderedSpinLock` takes the token for a lower level and hands out one for
+/// the lock's own level, borrowing the old one until the guard is gone.
+pub struct LockToken<'a, L> {
+    // the levels held are the current thread's, so the token stays on it
+    marker: PhantomData<(&'a mut L, *const ())>,
+}
+
+impl LockToken<'static, Unlocked> {
+    /// The starting token for a thread holding no ordered locks.
+    ///
+    /// # Safety
+    ///
+    /// Only one of these may be in use per thread at a time, otherwise a
+    /// second one could be used to lock past the order the first one
+    /// enforces.
+    pub unsafe fn new() -> Self {
+        Self {
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Spinlock at level `L` of a lock hierarchy checked by the type system:
+/// locking needs a token from a lower level, so taking locks out of order
+/// doesn't compile.
+///
+/// ```
+/// use spinlock::{Level1, Level2, LockToken, OrderedSpinLock};
+///
+/// let outer = OrderedSpinLock::<_, Level1>::new(0);
+/// let inner = OrderedSpinLock::<_, Level2>::new(0);
+///
+/// let mut token = unsafe { LockToken::new() };
+/// let (mut a, mut token) = outer.lock(&mut token);
+/// let (mut b, _) = inner.lock(&mut token);
+/// *a += 1;
+/// *b += 1;
+/// ```
+///
+/// ```compile_fail
+/// use spinlock::{Level1, Level2, LockToken, OrderedSpinLock};
+///
+/// let outer = OrderedSpinLock::<_, Level1>::new(0);
+/// let inner = OrderedSpinLock::<_, Level2>::new(0);
+///
+/// let mut token = unsafe { LockToken::new() };
+/// let (_b, mut token) = inner.lock(&mut token);
+/// let (_a, _) = outer.lock(&mut token);
+/// ```
+pub struct OrderedSpinLock<T: ?Sized, L> {
+    // fn() so the level doesn't affect Send/Sync, it's never stored
+    level: PhantomData<fn() -> L>,
+    inner: SpinLock<T>,
+}
+
+impl<T, L: Level> OrderedSpinLock<T, L> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            level: PhantomData,
+            inner: SpinLock::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized, L: Level> OrderedSpinLock<T, L> {
+    #[inline]
+    pub fn lock<'a, H: LowerThan<L>>(
+        &'a self,
+        _token: &'a mut LockToken<'_, H>,
+    ) -> (Guard<'a, T>, LockToken<'a, L>) {
+        let token = LockToken {
+            marker: PhantomData,
+        };
+        (self.inner.lock(), token)
+    }
+
+    #[inline]
+    pub fn try_lock<'a, H: LowerThan<L>>(
+        &'a self,
+        _token: &'a mut LockToken<'_, H>,
+    ) -> Option<(Guard<'a, T>, LockToken<'a, L>)> {
+        let token = LockToken {
+            marker: PhantomData,
+        };
+        self.inner.try_lock().map(|guard| (guard, token))
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+}
@@ -0,0 +1,210 @@
+use alloc::sync::Arc;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+
+use crate::{Backoff, Relax, SpinLock};
+
+impl<T: ?Sized, R: Relax> SpinLock<T, R> {
+    /// Like `lock`, but the guard keeps its own `Arc` to the lock instead of
+    /// borrowing it, so it's `'static` (for `'static` data) and can be moved
+    /// into spawned threads or tasks.
+    pub fn lock_arc(self: &Arc<Self>) -> OwnedGuard<T, R> {
+        self.raw.lock();
+        OwnedGuard {
+            lock: self.clone(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn try_lock_arc(self: &Arc<Self>) -> Option<OwnedGuard<T, R>> {
+        self.raw.try_lock().then(|| OwnedGuard {
+            lock: self.clone(),
+            marker: PhantomData,
+        })
+    }
+}
+
+pub struct OwnedGuard<T: ?Sized, R = Backoff> {
+    pub(crate) lock: Arc<SpinLock<T, R>>,
+    // Send and Sync are implemented below rather than following `lock`
+    pub(crate) marker: PhantomData<*const ()>,
+}
+
+// Like `Guard`, plus whatever it takes to move or share the `Arc`.
+unsafe impl<T: ?Sized + Send, R> Send for OwnedGuard<T, R> where Arc<SpinLock<T, R>>: Send {}
+unsafe impl<T: ?Sized + Sync, R> Sync for OwnedGuard<T, R> where Arc<SpinLock<T, R>>: Sync {}
+
+impl<T: ?Sized, R> OwnedGuard<T, R> {
+    /// The lock this guard holds.
+    pub fn lock(this: &Self) -> &Arc<SpinLock<T, R>> {
+        &this.lock
+    }
+
+    /// `Guard::map` for owned guards: still `'static`, but only gives access
+    /// to one part of the value, e.g. to hand a task a single field of shared
+    /// state.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use spinlock::{OwnedGuard, SpinLock};
+    ///
+    /// let state = Arc::new(SpinLock::new((0, String::new())));
+    /// let mut name = OwnedGuard::map(state.lock_arc(), |s| &mut s.1);
+    /// std::thread::spawn(move || name.push_str("worker")).join().unwrap();
+    /// assert_eq!(state.lock().1, "worker");
+    /// ```
+    pub fn map<U: ?Sized, F>(mut this: Self, f: F) -> OwnedMappedGuard<T, U, R>
+    where
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        // if f panics, `this` still unlocks on the way out
+        let value: *mut U = f(&mut *this);
+        OwnedMappedGuard {
+            lock: Self::into_arc(this),
+            value,
+            marker: PhantomData,
+        }
+    }
+
+    /// `Guard::try_map` for owned guards. If `f` returns `None` the original
+    /// guard comes back as the error, still locked.
+    pub fn try_map<U: ?Sized, F>(mut this: Self, f: F) -> Result<OwnedMappedGuard<T, U, R>, Self>
+    where
+        F: FnOnce(&mut T) -> Option<&mut U>,
+    {
+        let value: *mut U = match f(&mut *this) {
+            Some(value) => value,
+            None => return Err(this),
+        };
+        Ok(OwnedMappedGuard {
+            lock: Self::into_arc(this),
+            value,
+            marker: PhantomData,
+        })
+    }
+
+    // The Arc, with the lock left held.
+    fn into_arc(this: Self) -> Arc<SpinLock<T, R>> {
+        let this = ManuallyDrop::new(this);
+        // safety: `this` is never dropped, so the Arc is moved out rather
+        // than copied
+        unsafe { ptr::read(&this.lock) }
+    }
+}
+
+impl<T: ?Sized, R> Deref for OwnedGuard<T, R> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: very existence of guard
+        // guarantees we've exclusively locked the lock
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized, R> DerefMut for OwnedGuard<T, R> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: very existence of guard
+        // guarantees we've exclusively locked the lock
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug, R> fmt::Debug for OwnedGuard<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized, R> Drop for OwnedGuard<T, R> {
+    #[inline]
+    fn drop(&mut self) {
+        // safety: the guard is the proof we hold the lock, and it's going away
+        unsafe { self.lock.raw.unlock() }
+    }
+}
+
+/// A guard for part of an `Arc`-held `SpinLock`'s value, made by
+/// `OwnedGuard::map`.
+pub struct OwnedMappedGuard<T: ?Sized, U: ?Sized, R = Backoff> {
+    lock: Arc<SpinLock<T, R>>,
+    value: *mut U,
+    marker: PhantomData<*const ()>,
+}
+
+unsafe impl<T: ?Sized, U: ?Sized + Send, R> Send for OwnedMappedGuard<T, U, R> where
+    Arc<SpinLock<T, R>>: Send
+{
+}
+unsafe impl<T: ?Sized, U: ?Sized + Sync, R> Sync for OwnedMappedGuard<T, U, R> where
+    Arc<SpinLock<T, R>>: Sync
+{
+}
+
+impl<T: ?Sized, U: ?Sized, R> OwnedMappedGuard<T, U, R> {
+    /// Narrows the guard further, like `OwnedGuard::map`.
+    pub fn map<V: ?Sized, F>(mut this: Self, f: F) -> OwnedMappedGuard<T, V, R>
+    where
+        F: FnOnce(&mut U) -> &mut V,
+    {
+        let value: *mut V = f(&mut *this);
+        OwnedMappedGuard {
+            lock: Self::into_arc(this),
+            value,
+            marker: PhantomData,
+        }
+    }
+
+    /// Narrows the guard further, like `OwnedGuard::try_map`.
+    pub fn try_map<V: ?Sized, F>(mut this: Self, f: F) -> Result<OwnedMappedGuard<T, V, R>, Self>
+    where
+        F: FnOnce(&mut U) -> Option<&mut V>,
+    {
+        let value: *mut V = match f(&mut *this) {
+            Some(value) => value,
+            None => return Err(this),
+        };
+        Ok(OwnedMappedGuard {
+            lock: Self::into_arc(this),
+            value,
+            marker: PhantomData,
+        })
+    }
+
+    fn into_arc(this: Self) -> Arc<SpinLock<T, R>> {
+        let this = ManuallyDrop::new(this);
+        // safety: as in `OwnedGuard::into_arc`
+        unsafe { ptr::read(&this.lock) }
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, R> Deref for OwnedMappedGuard<T, U, R> {
+    type Target = U;
+    fn deref(&self) -> &U {
+        // safety: points into the locked value, and we still hold the lock
+        unsafe { &*self.value }
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, R> DerefMut for OwnedMappedGuard<T, U, R> {
+    fn deref_mut(&mut self) -> &mut U {
+        // safety: points into the locked value, and we still hold the lock
+        unsafe { &mut *self.value }
+    }
+}
+
+impl<T: ?Sized, U: ?Sized + fmt::Debug, R> fmt::Debug for OwnedMappedGuard<T, U, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, R> Drop for OwnedMappedGuard<T, U, R> {
+    #[inline]
+    fn drop(&mut self) {
+        // safety: the guard is the proof we hold the lock, and it's going away
+        unsafe { self.lock.raw.unlock() }
+    }
+}
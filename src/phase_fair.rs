@@ -0,0 +1,161 @@
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+
+use crate::sync::{Acquire, AtomicUsize, Relaxed, Release};
+use crate::Backoff;
+
+// The low bits of `rin` mark a writer: PRESENT while one is waiting for or
+// holding the lock, PHASE telling consecutive writers apart so readers
+// notice a change even when the next writer comes right after. The rest
+// counts readers, in steps of READER.
+const PHASE: usize = 1;
+const PRESENT: usize = 2;
+const WRITER_BITS: usize = PHASE | PRESENT;
+const READER: usize = 4;
+
+/// Phase-fair reader-writer lock (Brandenburg and Anderson's PF-T): reader
+/// and writer phases alternate, so writers are served in FIFO order and
+/// each one waits for at most one batch of readers, and readers for at most
+/// one writer, however high the pressure from the other side. `RwSpinLock`
+/// lets readers keep a writer out for as long as they keep coming.
+pub struct PhaseFairRwLock<T> {
+    // readers in, plus the writer bits
+    rin: AtomicUsize,
+    // readers out, in READER steps
+    rout: AtomicUsize,
+    // writer tickets taken and served
+    win: AtomicUsize,
+    wout: AtomicUsize,
+    value: UnsafeCell<T>,
+}
+
+// Same bound as RwSpinLock's: readers share &T across threads.
+unsafe impl<T> Sync for PhaseFairRwLock<T> where T: Send + Sync {}
+
+impl<T> PhaseFairRwLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            rin: AtomicUsize::new(0),
+            rout: AtomicUsize::new(0),
+            win: AtomicUsize::new(0),
+            wout: AtomicUsize::new(0),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline]
+    pub fn read(&self) -> PhaseFairReadGuard<'_, T> {
+        let writer = self.rin.fetch_add(READER, Acquire) & WRITER_BITS;
+        if writer != 0 {
+            self.read_slow(writer);
+        }
+        PhaseFairReadGuard { lock: self }
+    }
+
+    // Already counted in, so the writer won't start before us once it's
+    // done; only wait for the phase to move on.
+    #[cold]
+    #[inline(never)]
+    fn read_slow(&self, writer: usize) {
+        let mut backoff = Backoff::with_jitter();
+        while self.rin.load(Acquire) & WRITER_BITS == writer {
+            backoff.relax();
+        }
+    }
+
+    #[inline]
+    pub fn write(&self) -> PhaseFairWriteGuard<'_, T> {
+        let ticket = self.win.fetch_add(1, Relaxed);
+        if self.wout.load(Acquire) != ticket {
+            self.wait_writers(ticket);
+        }
+        // block new readers, then wait for the ones already in
+        let writer = PRESENT | (ticket & PHASE);
+        let readers = self.rin.fetch_add(writer, Acquire);
+        if self.rout.load(Acquire) != readers {
+            self.wait_readers(readers);
+        }
+        PhaseFairWriteGuard { lock: self }
+    }
+
+    // Waits for the writers ahead of our ticket.
+    #[cold]
+    #[inline(never)]
+    fn wait_writers(&self, ticket: usize) {
+        let mut backoff = Backoff::with_jitter();
+        while self.wout.load(Acquire) != ticket {
+            backoff.relax();
+        }
+    }
+
+    // Waits for the readers that came in before we blocked new ones.
+    #[cold]
+    #[inline(never)]
+    fn wait_readers(&self, readers: usize) {
+        let mut backoff = Backoff::with_jitter();
+        while self.rout.load(Acquire) != readers {
+            backoff.relax();
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T: Default> Default for PhaseFairRwLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+pub struct PhaseFairReadGuard<'a, T> {
+    lock: &'a PhaseFairRwLock<T>,
+}
+
+impl<T> Deref for PhaseFairReadGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: no writer is past its wait while a reader is counted in
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for PhaseFairReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.rout.fetch_add(READER, Release);
+    }
+}
+
+pub struct PhaseFairWriteGuard<'a, T> {
+    lock: &'a PhaseFairRwLock<T>,
+}
+
+impl<T> Deref for PhaseFairWriteGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: the writer waited for every reader to leave
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for PhaseFairWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: the writer waited for every reader to leave
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for PhaseFairWriteGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // lets the readers that came in meanwhile go, then the next writer
+        self.lock.rin.fetch_and(!WRITER_BITS, Release);
+        self.lock.wout.fetch_add(1, Release);
+    }
+}
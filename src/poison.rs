@@ -0,0 +1,165 @@
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::AtomicBool;
+use std::sync::{LockResult, PoisonError, TryLockError, TryLockResult};
+use std::thread;
+
+use crate::sync::Relaxed;
+use crate::{Guard, SpinLock};
+
+/// `SpinLock` with `std::sync::Mutex`-style poisoning: if a thread panics
+/// while holding the lock, later acquisitions report that the data may be
+/// in a broken state by returning `Err(PoisonError)`, which still carries
+/// the guard for anyone who wants to recover.
+///
+/// Plain `SpinLock` never poisons, so it pays for neither the flag nor the
+/// check; this type is only built with the (default) `poison` feature.
+pub struct PoisonSpinLock<T: ?Sized> {
+    poisoned: AtomicBool,
+    inner: SpinLock<T>,
+}
+
+impl<T> PoisonSpinLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            poisoned: AtomicBool::new(false),
+            inner: SpinLock::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> LockResult<T> {
+        let poisoned = self.is_poisoned();
+        let value = self.inner.into_inner();
+        if poisoned {
+            Err(PoisonError::new(value))
+        } else {
+            Ok(value)
+        }
+    }
+}
+
+impl<T: ?Sized> PoisonSpinLock<T> {
+    pub fn lock(&self) -> LockResult<PoisonGuard<'_, T>> {
+        self.wrap(self.inner.lock())
+    }
+
+    pub fn try_lock(&self) -> TryLockResult<PoisonGuard<'_, T>> {
+        match self.inner.try_lock() {
+            Some(guard) => Ok(self.wrap(guard)?),
+            None => Err(TryLockError::WouldBlock),
+        }
+    }
+
+    /// Takes the lock whether or not it's poisoned, for callers that don't
+    /// care about broken invariants.
+    pub fn lock_unpoisoned(&self) -> PoisonGuard<'_, T> {
+        self.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Relaxed)
+    }
+
+    /// Marks the data as fine again, once whoever clears it has restored its
+    /// invariants.
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Relaxed);
+    }
+
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        let poisoned = self.is_poisoned();
+        let value = self.inner.get_mut();
+        if poisoned {
+            Err(PoisonError::new(value))
+        } else {
+            Ok(value)
+        }
+    }
+
+    fn wrap<'a>(&'a self, guard: Guard<'a, T>) -> LockResult<PoisonGuard<'a, T>> {
+        let guard = PoisonGuard {
+            lock: self,
+            guard,
+            panicking: thread::panicking(),
+        };
+        if self.is_poisoned() {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
+    }
+}
+
+impl<T: Default> Default for PoisonSpinLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for PoisonSpinLock<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for PoisonSpinLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("PoisonSpinLock");
+        match self.inner.try_lock() {
+            Some(guard) => d.field("data", &&*guard),
+            None => d.field("data", &format_args!("<locked>")),
+        };
+        d.field("poisoned", &self.is_poisoned());
+        d.finish_non_exhaustive()
+    }
+}
+
+/// Held lock on a `PoisonSpinLock`. Formats as the value, like std's
+/// `MutexGuard`:
+///
+/// ```
+/// let lock = spinlock::PoisonSpinLock::new(1);
+/// let guard = lock.lock().unwrap();
+/// assert_eq!(format!("{guard:?} {guard}"), "1 1");
+/// ```
+pub struct PoisonGuard<'a, T: ?Sized> {
+    lock: &'a PoisonSpinLock<T>,
+    guard: Guard<'a, T>,
+    // Already unwinding when the lock was taken: that panic didn't happen
+    // while holding it, so it mustn't poison it either.
+    panicking: bool,
+}
+
+impl<T: ?Sized> Deref for PoisonGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for PoisonGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for PoisonGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for PoisonGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> Drop for PoisonGuard<'_, T> {
+    fn drop(&mut self) {
+        // runs before the inner guard unlocks, so the next holder sees it
+        if !self.panicking && thread::panicking() {
+            self.lock.poisoned.store(true, Relaxed);
+        }
+    }
+}
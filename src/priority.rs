@@ -0,0 +1,256 @@
+use core::cell::{Cell, UnsafeCell};
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+use core::sync::atomic::AtomicBool;
+
+use crate::sync::{Acquire, Relaxed, Release};
+use crate::{Backoff, RawSpinLock};
+
+/// Called when a waiter with a higher priority than the current holder
+/// queues up, with the holder's priority as last reported and the waiter's,
+/// so the application can raise the holder thread's OS priority for as long
+/// as it holds the lock (priority inheritance). Runs on the waiting thread.
+///
+/// The hook must not panic. If it does, the waiter still waits its turn and
+/// passes the lock straight on before the panic goes any further, since its
+/// node is queued and mustn't be freed until then.
+pub type InheritanceHook = fn(holder: u32, waiter: u32);
+
+/// Queue entry for one waiter on a `PriorityLock`, like `McsNode`.
+pub struct PriorityNode {
+    // both only touched with the queue lock held
+    next: Cell<*const PriorityNode>,
+    priority: Cell<u32>,
+    granted: AtomicBool,
+}
+
+impl PriorityNode {
+    pub const fn new() -> Self {
+        Self {
+            next: Cell::new(ptr::null()),
+            priority: Cell::new(0),
+            granted: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Default for PriorityNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Queued lock that hands off to the waiter with the highest priority on
+/// unlock, first come first served among equal priorities, so e.g. a
+/// real-time thread doesn't queue up behind background workers. Higher
+/// numbers are more urgent.
+pub struct PriorityLock<T> {
+    // short-held lock over `state`, taken to queue up and to hand off
+    queue: RawSpinLock,
+    state: UnsafeCell<State>,
+    hook: Option<InheritanceHook>,
+    value: UnsafeCell<T>,
+}
+
+struct State {
+    locked: bool,
+    // the holder's priority, raised to any more urgent waiter's
+    holder: u32,
+    // waiters by priority, highest first
+    head: *const PriorityNode,
+}
+
+unsafe impl<T: Send> Send for PriorityLock<T> {}
+unsafe impl<T: Send> Sync for PriorityLock<T> {}
+
+impl<T> PriorityLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self::build(None, value)
+    }
+
+    /// A lock that calls `hook` whenever a more urgent waiter queues up
+    /// behind the holder, see `InheritanceHook`.
+    pub const fn with_inheritance(hook: InheritanceHook, value: T) -> Self {
+        Self::build(Some(hook), value)
+    }
+
+    const fn build(hook: Option<InheritanceHook>, value: T) -> Self {
+        Self {
+            queue: RawSpinLock::new(),
+            state: UnsafeCell::new(State {
+                locked: false,
+                holder: 0,
+                head: ptr::null(),
+            }),
+            hook,
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn lock<'a>(&'a self, node: &'a mut PriorityNode, priority: u32) -> PriorityGuard<'a, T> {
+        // only shared references from here on, the unlocker grants through it
+        let node = &*node;
+        self.queue.lock();
+        // safety: the queue lock is held
+        let state = unsafe { &mut *self.state.get() };
+        if !state.locked {
+            state.locked = true;
+            state.holder = priority;
+            // safety: taken above
+            unsafe { self.queue.unlock() };
+        } else {
+            self.enqueue(state, node, priority);
+        }
+        PriorityGuard {
+            lock: self,
+            marker: PhantomData,
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn enqueue(&self, state: &mut State, node: &PriorityNode, priority: u32) {
+        node.priority.set(priority);
+        node.granted.store(false, Relaxed);
+        // behind everyone at least as urgent
+        let mut prev: Option<&PriorityNode> = None;
+        let mut next = state.head;
+        // safety: queued nodes stay alive until granted
+        while let Some(queued) = unsafe { next.as_ref() } {
+            if queued.priority.get() < priority {
+                break;
+            }
+            prev = Some(queued);
+            next = queued.next.get();
+        }
+        node.next.set(next);
+        match prev {
+            Some(prev) => prev.next.set(node),
+            None => state.head = node,
+        }
+        let boosted = match self.hook {
+            Some(_) if priority > state.holder => Some(mem::replace(&mut state.holder, priority)),
+            _ => None,
+        };
+        // safety: taken in lock
+        unsafe { self.queue.unlock() };
+        if let (Some(hook), Some(holder)) = (self.hook, boosted) {
+            let pass_on = PassOn { lock: self, node };
+            hook(holder, priority);
+            mem::forget(pass_on);
+        }
+
+        let mut backoff = Backoff::with_jitter();
+        while !node.granted.load(Acquire) {
+            backoff.snooze();
+        }
+    }
+
+    /// Single attempt, None means someone else holds the lock.
+    pub fn try_lock(&self, priority: u32) -> Option<PriorityGuard<'_, T>> {
+        self.queue.lock();
+        // safety: the queue lock is held
+        let state = unsafe { &mut *self.state.get() };
+        let locked = !state.locked;
+        if locked {
+            state.locked = true;
+            state.holder = priority;
+        }
+        // safety: taken above
+        unsafe { self.queue.unlock() };
+        locked.then(|| PriorityGuard {
+            lock: self,
+            marker: PhantomData,
+        })
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+// Unwinding out of a panicking hook would free the node while it's still in
+// the queue, so this waits for the lock to get to it and hands it on.
+struct PassOn<'a, T> {
+    lock: &'a PriorityLock<T>,
+    node: &'a PriorityNode,
+}
+
+impl<T> Drop for PassOn<'_, T> {
+    fn drop(&mut self) {
+        let mut backoff = Backoff::with_jitter();
+        while !self.node.granted.load(Acquire) {
+            backoff.snooze();
+        }
+        drop(PriorityGuard {
+            lock: self.lock,
+            marker: PhantomData,
+        });
+    }
+}
+
+/// Unlike most guards this one can't be sent to another thread: an
+/// `InheritanceHook` boosts the thread that holds the lock, and that boost
+/// would stay behind on the wrong thread.
+///
+/// ```compile_fail
+/// let lock = spinlock::PriorityLock::new(0);
+/// let mut node = spinlock::PriorityNode::new();
+/// std::thread::scope(|s| {
+///     let mut guard = lock.lock(&mut node, 1);
+///     s.spawn(move || *guard += 1);
+/// });
+/// ```
+pub struct PriorityGuard<'a, T> {
+    lock: &'a PriorityLock<T>,
+    // may be priority boosted, so the holder must stay the same thread
+    marker: PhantomData<*const ()>,
+}
+
+unsafe impl<T: Sync> Sync for PriorityGuard<'_, T> {}
+
+impl<T> Deref for PriorityGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: only the holder has a guard
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for PriorityGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: only the holder has a guard
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for PriorityGuard<'_, T> {
+    fn drop(&mut self) {
+        let lock = self.lock;
+        lock.queue.lock();
+        // safety: the queue lock is held
+        let state = unsafe { &mut *lock.state.get() };
+        let next = state.head;
+        if next.is_null() {
+            state.locked = false;
+        } else {
+            // safety: queued nodes stay alive until granted
+            let next = unsafe { &*next };
+            state.head = next.next.get();
+            state.holder = next.priority.get();
+        }
+        // safety: taken above
+        unsafe { lock.queue.unlock() };
+        if !next.is_null() {
+            // handed over without unlocking; the waiter may free its node as
+            // soon as it sees this
+            unsafe { (*next).granted.store(true, Release) };
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use core::mem::MaybeUninit;
+
+use crate::{Backoff, SpinLock};
+
+/// Bounded MPMC queue holding up to `N` values inline, a ring buffer behind
+/// one spinlock. Never allocates, so it fits `no_std` targets and interrupt
+/// handlers passing work to a main loop (with `try_push`/`try_pop` from the
+/// handler side, see `IrqSpinLock` for why blocking there is a bad idea).
+pub struct SpinQueue<T, const N: usize> {
+    ring: SpinLock<Ring<T, N>>,
+}
+
+struct Ring<T, const N: usize> {
+    slots: [MaybeUninit<T>; N],
+    // index of the oldest value
+    head: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> SpinQueue<T, N> {
+    /// `N` must not be 0.
+    pub const fn new() -> Self {
+        const { assert!(N > 0, "a SpinQueue needs room for at least one value") };
+        Self {
+            ring: SpinLock::new(Ring {
+                slots: [const { MaybeUninit::uninit() }; N],
+                head: 0,
+                len: 0,
+            }),
+        }
+    }
+
+    /// Adds `value` at the back, spinning while the queue is full.
+    pub fn push(&self, mut value: T) {
+        let mut backoff = Backoff::with_jitter();
+        loop {
+            match self.try_push(value) {
+                Ok(()) => return,
+                Err(v) => value = v,
+            }
+            backoff.relax();
+        }
+    }
+
+    /// Adds `value` at the back, or hands it back if the queue is full.
+    pub fn try_push(&self, value: T) -> Result<(), T> {
+        let mut ring = self.ring.lock();
+        if ring.len == N {
+            return Err(value);
+        }
+        let tail = (ring.head + ring.len) % N;
+        ring.slots[tail].write(value);
+        ring.len += 1;
+        Ok(())
+    }
+
+    /// Takes the value at the front, spinning while the queue is empty.
+    pub fn pop(&self) -> T {
+        let mut backoff = Backoff::with_jitter();
+        loop {
+            if let Some(value) = self.try_pop() {
+                return value;
+            }
+            backoff.relax();
+        }
+    }
+
+    pub fn try_pop(&self) -> Option<T> {
+        let mut ring = self.ring.lock();
+        if ring.len == 0 {
+            return None;
+        }
+        let head = ring.head;
+        ring.head = (head + 1) % N;
+        ring.len -= 1;
+        // safety: slots from head on for len values are initialized, and
+        // this one just left that range
+        Some(unsafe { ring.slots[head].assume_init_read() })
+    }
+
+    /// Only a snapshot, other threads may push or pop right after.
+    pub fn len(&self) -> usize {
+        self.ring.lock().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<T, const N: usize> Drop for Ring<T, N> {
+    fn drop(&mut self) {
+        for i in 0..self.len {
+            // safety: those are exactly the initialized slots
+            unsafe { self.slots[(self.head + i) % N].assume_init_drop() };
+        }
+    }
+}
+
+impl<T, const N: usize> Default for SpinQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,436 @@
+use core::marker::PhantomData;
+use core::sync::atomic::AtomicBool;
+#[cfg(feature = "registry")]
+use core::sync::atomic::AtomicPtr;
+#[cfg(any(feature = "stats", feature = "tracing", feature = "watchdog"))]
+use core::sync::atomic::AtomicU64;
+
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+use crate::sync::{fence, Acquire, AtomicUsize, Relaxed, Release};
+use crate::{Backoff, Relax};
+
+/// The bare lock state behind `SpinLock`, with no data attached. For
+/// protecting data that can't live inside the lock, like FFI structs or MMIO
+/// regions; nothing ties an acquisition to the data, so it's up to the
+/// caller to only touch that data while holding the lock.
+///
+/// With the `debug-detect` feature, a thread locking a lock it already holds
+/// panics instead of spinning forever. This assumes locks are released on the
+/// thread that took them; a guard sent to another thread before relocking
+/// from the original one will be reported as a deadlock too.
+pub struct RawSpinLock<R = Backoff> {
+    // bit 0 is the lock, the rest counts unlocks, so that optimistic readers
+    // like `SpinLock::load` can tell whether anyone held it in the meantime
+    state: AtomicUsize,
+    // thread_id of the holder, 0 while unlocked
+    #[cfg(feature = "debug-detect")]
+    owner: AtomicUsize,
+    #[cfg(feature = "stats")]
+    stats: crate::stats::Counters,
+    // clock::now() when the current holder took the lock, only touched by it
+    #[cfg(any(feature = "stats", feature = "tracing", feature = "watchdog"))]
+    locked_at: AtomicU64,
+    #[cfg(any(
+        feature = "tracing",
+        feature = "watchdog",
+        feature = "registry",
+        feature = "diagnostics"
+    ))]
+    name: Option<&'static str>,
+    // who holds it, for `lock_timeout`'s error
+    #[cfg(feature = "diagnostics")]
+    holder: crate::diagnostics::Slot,
+    // null until a named lock is first locked, then holds a strong reference
+    #[cfg(feature = "registry")]
+    entry: AtomicPtr<crate::registry::Entry>,
+    // fn() so the strategy doesn't affect Send/Sync, it's never stored
+    relax: PhantomData<fn() -> R>,
+}
+
+impl RawSpinLock {
+    pub const fn new() -> Self {
+        Self::with_relax()
+    }
+
+    /// A lock with a name for diagnostics, such as the events of the
+    /// `tracing` feature, `watchdog` reports or the `registry`. Without any
+    /// such feature the name is ignored.
+    pub const fn new_named(name: &'static str) -> Self {
+        Self::build(Some(name))
+    }
+}
+
+impl<R: Relax> RawSpinLock<R> {
+    /// `new` for a non-default relax strategy, e.g.
+    /// `RawSpinLock::<Spin>::with_relax()`.
+    pub const fn with_relax() -> Self {
+        Self::build(None)
+    }
+
+    const fn build(name: Option<&'static str>) -> Self {
+        #[cfg(not(any(
+            feature = "tracing",
+            feature = "watchdog",
+            feature = "registry",
+            feature = "diagnostics"
+        )))]
+        let _ = name;
+        Self {
+            state: AtomicUsize::new(0),
+            #[cfg(feature = "debug-detect")]
+            owner: AtomicUsize::new(0),
+            #[cfg(feature = "stats")]
+            stats: crate::stats::Counters::new(),
+            #[cfg(any(feature = "stats", feature = "tracing", feature = "watchdog"))]
+            locked_at: AtomicU64::new(0),
+            #[cfg(any(
+                feature = "tracing",
+                feature = "watchdog",
+                feature = "registry",
+                feature = "diagnostics"
+            ))]
+            name,
+            #[cfg(feature = "diagnostics")]
+            holder: crate::diagnostics::Slot::new(),
+            #[cfg(feature = "registry")]
+            entry: AtomicPtr::new(core::ptr::null_mut()),
+            relax: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn lock(&self) {
+        if !self.acquire() {
+            self.lock_slow();
+        }
+        self.acquired();
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn lock_slow(&self) {
+        #[cfg(feature = "debug-detect")]
+        if self.owner.load(Relaxed) == crate::thread_id::current() {
+            panic!("deadlock: this thread already holds the spinlock it is trying to lock");
+        }
+        #[cfg(feature = "tracing")]
+        let start = std::time::Instant::now();
+        let mut relax = R::default();
+        #[cfg(feature = "deadlock_detection")]
+        let mut wait = crate::deadlock::Wait::new(self);
+        #[cfg(feature = "stats")]
+        let mut spins = 0;
+        #[cfg(feature = "registry")]
+        let entry = self.entry();
+        #[cfg(feature = "registry")]
+        if let Some(entry) = entry {
+            entry.wait_start();
+        }
+        // test-and-test-and-set: only try to write to the cache line once it
+        // looks free, waiting on a shared copy of it in the meantime
+        loop {
+            while self.is_locked() {
+                #[cfg(feature = "deadlock_detection")]
+                wait.tick();
+                #[cfg(feature = "stats")]
+                {
+                    spins += 1;
+                }
+                relax.relax();
+            }
+            if self.acquire() {
+                #[cfg(feature = "stats")]
+                self.stats.spun(spins);
+                #[cfg(feature = "registry")]
+                if let Some(entry) = entry {
+                    entry.wait_end();
+                }
+                #[cfg(feature = "tracing")]
+                crate::instrument::contended(self.name, self.addr(), start.elapsed());
+                return;
+            }
+        }
+    }
+
+    /// `lock` that gives up once `cancel` is set, so shutdown doesn't hang
+    /// on a lock whose holder is gone. false means it was cancelled. A free
+    /// lock is still taken even if `cancel` is already set.
+    pub fn lock_with_cancel(&self, cancel: &AtomicBool) -> bool {
+        self.lock_while(|| !cancel.load(Relaxed))
+    }
+
+    // Spins until the lock is taken (true) or `keep_waiting` says to stop.
+    // Goes through the same debugging hooks as lock_slow, except that
+    // `debug-detect` doesn't panic: waiting on a lock this thread holds only
+    // lasts until it gives up.
+    fn lock_while(&self, mut keep_waiting: impl FnMut() -> bool) -> bool {
+        if self.acquire() {
+            self.acquired();
+            return true;
+        }
+        #[cfg(feature = "tracing")]
+        let start = std::time::Instant::now();
+        let mut relax = R::default();
+        #[cfg(feature = "deadlock_detection")]
+        let mut wait = crate::deadlock::Wait::new(self);
+        #[cfg(feature = "stats")]
+        let mut spins = 0;
+        #[cfg(feature = "registry")]
+        let entry = self.entry();
+        #[cfg(feature = "registry")]
+        if let Some(entry) = entry {
+            entry.wait_start();
+        }
+        let locked = loop {
+            if !keep_waiting() {
+                break false;
+            }
+            #[cfg(feature = "deadlock_detection")]
+            wait.tick();
+            #[cfg(feature = "stats")]
+            {
+                spins += 1;
+            }
+            relax.relax();
+            if !self.is_locked() && self.acquire() {
+                break true;
+            }
+        };
+        #[cfg(feature = "registry")]
+        if let Some(entry) = entry {
+            entry.wait_end();
+        }
+        if locked {
+            #[cfg(feature = "stats")]
+            self.stats.spun(spins);
+            #[cfg(feature = "tracing")]
+            crate::instrument::contended(self.name, self.addr(), start.elapsed());
+            self.acquired();
+        }
+        locked
+    }
+}
+
+/// `lock` variants that give up at a deadline. false means they timed out.
+#[cfg(feature = "std")]
+impl<R: Relax> RawSpinLock<R> {
+    pub fn try_lock_for(&self, timeout: Duration) -> bool {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.try_lock_until(deadline),
+            // too far out to ever happen
+            None => {
+                self.lock();
+                true
+            }
+        }
+    }
+
+    pub fn try_lock_until(&self, deadline: Instant) -> bool {
+        self.lock_while(|| Instant::now() < deadline)
+    }
+
+    /// `try_lock_for` that says who was in the way when it gives up, with
+    /// the `diagnostics` feature.
+    #[cfg(feature = "diagnostics")]
+    pub fn lock_timeout(&self, timeout: Duration) -> Result<(), crate::diagnostics::TimedOut> {
+        let start = Instant::now();
+        if self.try_lock_for(timeout) {
+            return Ok(());
+        }
+        Err(self
+            .holder
+            .timed_out(self.name, self.addr(), start.elapsed()))
+    }
+}
+
+// None of these ever wait, so they work with any strategy.
+impl<R> RawSpinLock<R> {
+    // Single attempt, never spins. false means someone else holds the lock.
+    #[inline]
+    pub fn try_lock(&self) -> bool {
+        let locked = self.acquire();
+        if locked {
+            self.acquired();
+        } else {
+            #[cfg(feature = "stats")]
+            self.stats.try_lock_failed();
+        }
+        locked
+    }
+
+    /// Only a snapshot, see `SpinLock::is_locked`.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.state.load(Relaxed) & 1 != 0
+    }
+
+    // Sets the lock bit, true if it wasn't set before. Setting it again
+    // while held changes nothing, so failing is harmless.
+    #[inline]
+    fn acquire(&self) -> bool {
+        let acquired = self.state.fetch_or(1, Acquire) & 1 == 0;
+        // an optimistic reader that sees any write made under the lock must
+        // also see it taken, see `version`; only a compiler barrier on x86
+        if acquired {
+            fence(Release);
+        }
+        acquired
+    }
+
+    // The current state for an optimistic read: even means unlocked, and an
+    // unchanged version after the read means nobody held the lock meanwhile.
+    #[inline]
+    pub(crate) fn version(&self) -> usize {
+        self.state.load(Acquire)
+    }
+
+    // Whether the lock is still at `version`, after reading what it protects.
+    #[inline]
+    pub(crate) fn validate(&self, version: usize) -> bool {
+        // keeps the reads being validated from moving after the check
+        fence(Acquire);
+        self.state.load(Relaxed) == version
+    }
+
+    /// # Safety
+    ///
+    /// The caller must hold the lock, and nothing it protects may be
+    /// accessed through that acquisition anymore.
+    #[inline]
+    pub unsafe fn unlock(&self) {
+        self.released();
+        // read before unlocking, the next holder overwrites it
+        #[cfg(any(feature = "stats", feature = "tracing", feature = "watchdog"))]
+        let held = core::time::Duration::from_nanos(
+            crate::clock::now().saturating_sub(self.locked_at.load(Relaxed)),
+        );
+        #[cfg(feature = "stats")]
+        self.stats.released(held);
+        // only the holder changes the state while it's locked, so a plain
+        // store can clear the bit and bump the version at once
+        let state = self.state.load(Relaxed);
+        self.state.store(state.wrapping_add(1), Release);
+        // reported after unlocking, so a subscriber or hook that takes this
+        // lock itself doesn't deadlock
+        #[cfg(feature = "tracing")]
+        crate::instrument::released(self.name, self.addr(), held);
+        #[cfg(feature = "watchdog")]
+        crate::watchdog::released(self.name, self.addr(), held);
+    }
+
+    /// Contention counters since the lock was created.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::LockStats {
+        self.stats.snapshot()
+    }
+
+    /// Who holds the lock right now, with the `diagnostics` feature. Only a
+    /// snapshot, like `is_locked`.
+    #[cfg(feature = "diagnostics")]
+    pub fn owner(&self) -> Option<crate::diagnostics::Owner> {
+        self.holder.owner()
+    }
+
+    // Bookkeeping for the debugging features, right after taking the lock
+    // and right before releasing it.
+    #[inline]
+    fn acquired(&self) {
+        // Only the thread that just took the lock writes this, and only it
+        // can ever read back its own id, so no ordering is needed.
+        #[cfg(feature = "debug-detect")]
+        self.owner.store(crate::thread_id::current(), Relaxed);
+        #[cfg(feature = "deadlock_detection")]
+        crate::deadlock::acquired(self as *const Self as usize);
+        #[cfg(feature = "stats")]
+        self.stats.acquired();
+        #[cfg(any(feature = "stats", feature = "tracing", feature = "watchdog"))]
+        self.locked_at.store(crate::clock::now(), Relaxed);
+        #[cfg(feature = "registry")]
+        if let Some(entry) = self.entry() {
+            entry.acquired();
+        }
+        #[cfg(feature = "diagnostics")]
+        self.holder.acquired();
+    }
+
+    #[inline]
+    fn released(&self) {
+        #[cfg(feature = "debug-detect")]
+        self.owner.store(0, Relaxed);
+        #[cfg(feature = "deadlock_detection")]
+        crate::deadlock::released(self as *const Self as usize);
+        #[cfg(feature = "registry")]
+        if let Some(entry) = self.entry() {
+            entry.released();
+        }
+        #[cfg(feature = "diagnostics")]
+        self.holder.released();
+    }
+
+    // The registry entry of a named lock, registering it on first use.
+    #[cfg(feature = "registry")]
+    fn entry(&self) -> Option<&crate::registry::Entry> {
+        use crate::sync::AcqRel;
+        use std::sync::Arc;
+
+        let name = self.name?;
+        let mut entry = self.entry.load(Acquire);
+        if entry.is_null() {
+            let new = Arc::into_raw(crate::registry::Entry::register(name)) as *mut _;
+            match self
+                .entry
+                .compare_exchange(core::ptr::null_mut(), new, AcqRel, Acquire)
+            {
+                Ok(_) => entry = new,
+                Err(existing) => {
+                    // safety: ours never got shared, so this is the only reference
+                    drop(unsafe { Arc::from_raw(new) });
+                    entry = existing;
+                }
+            }
+        }
+        // safety: the lock holds a strong reference until it's dropped
+        Some(unsafe { &*entry })
+    }
+
+    #[cfg(any(feature = "tracing", feature = "watchdog", feature = "diagnostics"))]
+    fn addr(&self) -> *const () {
+        self as *const Self as *const ()
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "registry"))]
+impl<R> Drop for RawSpinLock<R> {
+    fn drop(&mut self) {
+        // Nobody can hold a lock that's going away, unless a guard was
+        // forgotten or a force_unlock is missing. Not while unwinding though,
+        // that would turn the panic that got us here into an abort.
+        #[cfg(debug_assertions)]
+        {
+            #[cfg(feature = "std")]
+            let unwinding = std::thread::panicking();
+            #[cfg(not(feature = "std"))]
+            let unwinding = false;
+            if self.is_locked() && !unwinding {
+                panic!("spinlock dropped while locked, was a guard leaked?");
+            }
+        }
+
+        #[cfg(feature = "registry")]
+        {
+            let entry = *self.entry.get_mut();
+            if !entry.is_null() {
+                // safety: the strong reference taken in entry(), given up once
+                drop(unsafe { std::sync::Arc::from_raw(entry) });
+            }
+        }
+    }
+}
+
+impl<R: Relax> Default for RawSpinLock<R> {
+    fn default() -> Self {
+        Self::with_relax()
+    }
+}
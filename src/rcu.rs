@@ -0,0 +1,109 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering::SeqCst};
+
+use crate::sync::Relaxed;
+use crate::{Backoff, SpinLock};
+
+/// Read-mostly shared value, such as a hot-reloaded config: readers get an
+/// `Arc` snapshot without taking a lock, writers swap in new versions one at
+/// a time behind a spinlock.
+///
+/// The current and the previous version live in two slots. A write replaces
+/// the older slot, waiting for readers still cloning out of it (a matter of
+/// one refcount increment), then flips the generation, so readers never wait
+/// on writers and only retry if a write flipped the generation under them.
+/// The previous version is kept alive until the next write.
+pub struct RcuCell<T> {
+    // Arc::into_raw pointers, the current version in slots[generation % 2]
+    slots: [AtomicPtr<T>; 2],
+    // readers in the middle of cloning out of each slot
+    readers: [AtomicUsize; 2],
+    generation: AtomicUsize,
+    writer: SpinLock<()>,
+}
+
+unsafe impl<T: Send + Sync> Send for RcuCell<T> {}
+unsafe impl<T: Send + Sync> Sync for RcuCell<T> {}
+
+impl<T> RcuCell<T> {
+    pub fn new(value: T) -> Self {
+        let value = Arc::into_raw(Arc::new(value)) as *mut T;
+        // safety: the extra count is the one slot 1 owns
+        unsafe { Arc::increment_strong_count(value) };
+        Self {
+            slots: [AtomicPtr::new(value), AtomicPtr::new(value)],
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            generation: AtomicUsize::new(0),
+            writer: SpinLock::new(()),
+        }
+    }
+
+    /// The current version. Never blocks on a writer.
+    pub fn load(&self) -> Arc<T> {
+        loop {
+            let generation = self.generation.load(SeqCst);
+            let slot = generation % 2;
+            self.readers[slot].fetch_add(1, SeqCst);
+            // Still the same generation means no write has started replacing
+            // this slot: writers only touch it after seeing our count at 0.
+            if self.generation.load(SeqCst) == generation {
+                let value = self.slots[slot].load(SeqCst);
+                // safety: the slot owns a count and can't drop it while we're
+                // registered as readers
+                let value = unsafe {
+                    Arc::increment_strong_count(value);
+                    Arc::from_raw(value)
+                };
+                self.readers[slot].fetch_sub(1, SeqCst);
+                return value;
+            }
+            self.readers[slot].fetch_sub(1, SeqCst);
+        }
+    }
+
+    /// Makes `value` the current version. Readers still holding older
+    /// snapshots keep them.
+    pub fn store(&self, value: T) {
+        let _writer = self.writer.lock();
+        self.publish(Arc::new(value));
+    }
+
+    /// Replaces the value with `f` of the current one. Writes are serialized,
+    /// so no update made concurrently gets lost.
+    pub fn update<F: FnOnce(&T) -> T>(&self, f: F) {
+        let _writer = self.writer.lock();
+        let current = self.load();
+        self.publish(Arc::new(f(&current)));
+    }
+
+    // Caller must hold the writer lock.
+    fn publish(&self, value: Arc<T>) {
+        let generation = self.generation.load(Relaxed);
+        let slot = (generation + 1) % 2;
+        // Only readers that saw the generation before the last write can be
+        // here, and they're about to notice the change and leave.
+        let mut backoff = Backoff::with_jitter();
+        while self.readers[slot].load(SeqCst) != 0 {
+            backoff.snooze();
+        }
+        let old = self.slots[slot].swap(Arc::into_raw(value) as *mut T, SeqCst);
+        self.generation.store(generation.wrapping_add(1), SeqCst);
+        // safety: the count the slot owned, and no reader is using it
+        drop(unsafe { Arc::from_raw(old) });
+    }
+}
+
+impl<T> Drop for RcuCell<T> {
+    fn drop(&mut self) {
+        for slot in &mut self.slots {
+            // safety: each slot owns one count
+            drop(unsafe { Arc::from_raw(*slot.get_mut()) });
+        }
+    }
+}
+
+impl<T: Default> Default for RcuCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
@@ -0,0 +1,147 @@
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::sync::atomic::AtomicUsize;
+
+use crate::sync::{Acquire, Relaxed, Release};
+use crate::{thread_id, Backoff};
+
+/// Spinlock that the thread holding it can lock again without deadlocking.
+/// Since several guards on the same thread can be alive at once, guards only
+/// give shared access; use a `Cell` or `RefCell` inside for mutation.
+pub struct ReentrantSpinLock<T: ?Sized> {
+    // thread_id of the holder, 0 while unlocked
+    owner: AtomicUsize,
+    // only touched by the owning thread
+    count: UnsafeCell<usize>,
+    pub(crate) value: UnsafeCell<T>,
+}
+
+// Guards on different threads never coexist, so T only needs to be Send, but
+// sharing &T within the owning thread is still fine for !Sync T like RefCell.
+unsafe impl<T: ?Sized + Send> Sync for ReentrantSpinLock<T> {}
+
+impl<T> ReentrantSpinLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            owner: AtomicUsize::new(0),
+            count: UnsafeCell::new(0),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> ReentrantSpinLock<T> {
+    #[inline]
+    pub fn lock(&self) -> ReentrantGuard<'_, T> {
+        let id = thread_id::current();
+        if self.owner.load(Relaxed) != id
+            && self
+                .owner
+                .compare_exchange_weak(0, id, Acquire, Relaxed)
+                .is_err()
+        {
+            self.lock_slow(id);
+        }
+        // safety: we own the lock now, and count is only touched by the owner
+        unsafe { self.increment() };
+        ReentrantGuard {
+            lock: self,
+            marker: PhantomData,
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn lock_slow(&self, id: usize) {
+        let mut backoff = Backoff::with_jitter();
+        loop {
+            while self.owner.load(Relaxed) != 0 {
+                backoff.relax();
+            }
+            if self
+                .owner
+                .compare_exchange_weak(0, id, Acquire, Relaxed)
+                .is_ok()
+            {
+                return;
+            }
+        }
+    }
+
+    // Succeeds if the lock is free or already held by this thread.
+    #[inline]
+    pub fn try_lock(&self) -> Option<ReentrantGuard<'_, T>> {
+        let id = thread_id::current();
+        if self.owner.load(Relaxed) != id
+            && self
+                .owner
+                .compare_exchange(0, id, Acquire, Relaxed)
+                .is_err()
+        {
+            return None;
+        }
+        // safety: we own the lock now, and count is only touched by the owner
+        unsafe { self.increment() };
+        Some(ReentrantGuard {
+            lock: self,
+            marker: PhantomData,
+        })
+    }
+
+    // Safety: the calling thread must own the lock
+    #[inline]
+    unsafe fn increment(&self) {
+        let count = &mut *self.count.get();
+        *count = count
+            .checked_add(1)
+            .expect("lock count overflow in reentrant spinlock");
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T: Default> Default for ReentrantSpinLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for ReentrantSpinLock<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+pub struct ReentrantGuard<'a, T: ?Sized> {
+    lock: &'a ReentrantSpinLock<T>,
+    // the lock is owned by this thread, so the guard must stay on it
+    marker: PhantomData<*const ()>,
+}
+
+impl<T: ?Sized> Deref for ReentrantGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: only guards on the owning thread exist, and they all only
+        // hand out shared references
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for ReentrantGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // safety: the guard is the proof this thread owns the lock
+        let count = unsafe { &mut *self.lock.count.get() };
+        *count -= 1;
+        if *count == 0 {
+            self.lock.owner.store(0, Release);
+        }
+    }
+}
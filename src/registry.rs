@@ -0,0 +1,119 @@
+//! Registry of named locks, with the `registry` feature.
+//!
+//! Every lock made with `SpinLock::new_named` (or `RawSpinLock::new_named`)
+//! enters itself here when first locked, so `snapshot` can list what it's
+//! doing right now:
+//!
+//! ```no_run
+//! # #[cfg(feature = "registry")] {
+//! static POOL: spinlock::SpinLock<Vec<u8>> = spinlock::SpinLock::new_named("buffer_pool", Vec::new());
+//!
+//! let _guard = POOL.lock();
+//! for lock in spinlock::registry::snapshot() {
+//!     println!("{lock}");
+//! }
+//! # }
+//! ```
+//!
+//! `snapshot` takes a mutex, so on a signal like SIGUSR1 call it from a
+//! thread that waits for the signal rather than from the handler itself.
+//! Locks leave the registry when they're dropped.
+
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize};
+use std::sync::{Arc, Mutex, Weak};
+use std::vec::Vec;
+
+use crate::sync::Relaxed;
+
+static LOCKS: Mutex<Vec<Weak<Entry>>> = Mutex::new(Vec::new());
+
+// Shared between a named lock and the registry. Lives on the heap, since the
+// lock itself may move after it's registered.
+pub(crate) struct Entry {
+    name: &'static str,
+    locked: AtomicBool,
+    waiters: AtomicUsize,
+    acquisitions: AtomicU64,
+    contentions: AtomicU64,
+}
+
+impl Entry {
+    pub(crate) fn register(name: &'static str) -> Arc<Self> {
+        let entry = Arc::new(Self {
+            name,
+            locked: AtomicBool::new(false),
+            waiters: AtomicUsize::new(0),
+            acquisitions: AtomicU64::new(0),
+            contentions: AtomicU64::new(0),
+        });
+        let mut locks = LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+        locks.retain(|lock| lock.strong_count() != 0);
+        locks.push(Arc::downgrade(&entry));
+        entry
+    }
+
+    #[inline]
+    pub(crate) fn acquired(&self) {
+        self.locked.store(true, Relaxed);
+        self.acquisitions.fetch_add(1, Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn released(&self) {
+        self.locked.store(false, Relaxed);
+    }
+
+    pub(crate) fn wait_start(&self) {
+        self.waiters.fetch_add(1, Relaxed);
+        self.contentions.fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn wait_end(&self) {
+        self.waiters.fetch_sub(1, Relaxed);
+    }
+}
+
+/// What one named lock was doing when `snapshot` looked at it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockInfo {
+    pub name: &'static str,
+    pub locked: bool,
+    /// Threads spinning on the lock.
+    pub waiters: usize,
+    pub acquisitions: u64,
+    /// Acquisitions that found the lock held and had to spin.
+    pub contentions: u64,
+}
+
+impl fmt::Display for LockInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}, {} waiting, {} acquisitions ({} contended)",
+            self.name,
+            if self.locked { "locked" } else { "unlocked" },
+            self.waiters,
+            self.acquisitions,
+            self.contentions
+        )
+    }
+}
+
+/// Every live named lock that has been locked at least once, oldest first.
+/// Each lock's fields are read separately, so they may not quite agree with
+/// each other.
+pub fn snapshot() -> Vec<LockInfo> {
+    let locks = LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+    locks
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|entry| LockInfo {
+            name: entry.name,
+            locked: entry.locked.load(Relaxed),
+            waiters: entry.waiters.load(Relaxed),
+            acquisitions: entry.acquisitions.load(Relaxed),
+            contentions: entry.contentions.load(Relaxed),
+        })
+        .collect()
+}
@@ -0,0 +1,63 @@
+use crate::Backoff;
+
+/// What a thread does between two failed attempts to take a lock. A fresh
+/// value is created (via `Default`) for every contended acquisition, so
+/// strategies can keep per-wait state such as a backoff step.
+pub trait Relax: Default {
+    fn relax(&mut self);
+}
+
+/// Busy-waits with a single `spin_loop()` hint per attempt.
+#[derive(Default)]
+pub struct Spin;
+
+impl Relax for Spin {
+    #[inline]
+    fn relax(&mut self) {
+        crate::sync::spin_loop();
+    }
+}
+
+/// Gives the rest of the time slice back to the OS scheduler on every
+/// attempt, for oversubscribed machines where the holder may not be running.
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub struct Yield;
+
+#[cfg(feature = "std")]
+impl Relax for Yield {
+    #[inline]
+    fn relax(&mut self) {
+        crate::sync::yield_now();
+    }
+}
+
+/// Spins `SPINS` times, then yields to the scheduler on every further
+/// attempt, e.g. `SpinLock<T, SpinThenYield<100>>`. Pure spinning can wait
+/// out a whole time slice when the holder has been descheduled.
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub struct SpinThenYield<const SPINS: u32> {
+    spins: u32,
+}
+
+#[cfg(feature = "std")]
+impl<const SPINS: u32> Relax for SpinThenYield<SPINS> {
+    #[inline]
+    fn relax(&mut self) {
+        if self.spins < SPINS {
+            self.spins += 1;
+            crate::sync::spin_loop();
+        } else {
+            crate::sync::yield_now();
+        }
+    }
+}
+
+/// The default: jittered exponential backoff, see `Backoff`.
+impl Relax for Backoff {
+    #[inline]
+    fn relax(&mut self) {
+        Backoff::relax(self);
+    }
+}
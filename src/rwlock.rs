@@ -0,0 +1,870 @@
+use core::borrow::Borrow;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+use crate::sync::{Acquire, AtomicUsize, Relaxed, Release};
+use crate::Backoff;
+
+// Lowest bit is set while a writer holds the lock, the next one while an
+// upgradable reader does. The next three are only used by some policies:
+// a writer is waiting, readers are waiting behind one, and it's those
+// readers' turn. The rest counts plain readers.
+const WRITER: usize = 1;
+const UPGRADABLE: usize = 2;
+const WRITER_WAITING: usize = 4;
+const READER_WAITING: usize = 8;
+const READER_TURN: usize = 16;
+const READER: usize = 32;
+
+/// Who gets the lock first when readers and writers both want it, see
+/// `RwSpinLock::with_policy`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RwPolicy {
+    /// Readers get in whenever no writer holds the lock, the cheapest
+    /// policy, but a steady stream of readers can keep writers out forever.
+    #[default]
+    ReaderPreferred,
+    /// A waiting writer keeps new readers out, so writers never starve but
+    /// readers can under a steady stream of writers.
+    WriterPreferred,
+    /// Like `WriterPreferred`, except that the readers a writer kept out get
+    /// in before the next writer, and so do readers that turn up before
+    /// those are all in. Neither side starves, but there are no bounds on
+    /// how long one waits; `PhaseFairRwLock` gives those.
+    Fair,
+}
+
+/// The bare lock state behind `RwSpinLock`, with no data attached.
+pub struct RawRwSpinLock {
+    pub(crate) state: AtomicUsize,
+    // Fair only: readers a writer is keeping out, their turn lasts until
+    // they're all in
+    blocked: AtomicUsize,
+    policy: RwPolicy,
+}
+
+impl RawRwSpinLock {
+    pub const fn new() -> Self {
+        Self::with_policy(RwPolicy::ReaderPreferred)
+    }
+
+    pub const fn with_policy(policy: RwPolicy) -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            blocked: AtomicUsize::new(0),
+            policy,
+        }
+    }
+
+    pub const fn policy(&self) -> RwPolicy {
+        self.policy
+    }
+
+    #[inline]
+    pub(crate) fn is_locked(&self) -> bool {
+        let state = self.state.load(Relaxed);
+        state & (WRITER | UPGRADABLE) != 0 || state >= READER
+    }
+
+    #[inline]
+    pub(crate) fn is_write_locked(&self) -> bool {
+        self.state.load(Relaxed) & WRITER != 0
+    }
+
+    #[inline]
+    pub(crate) fn reader_count(&self) -> usize {
+        let state = self.state.load(Relaxed);
+        state / READER + (state & UPGRADABLE != 0) as usize
+    }
+
+    // Whether a reader may come in. Upgradable readers also need the
+    // upgradable bit clear. On their turn, readers get in even if a writer
+    // has flagged itself since, or that writer would wait for the turn to
+    // end and the readers for the writer.
+    #[inline]
+    fn admits_reader(&self, state: usize) -> bool {
+        match self.policy {
+            RwPolicy::ReaderPreferred => state & WRITER == 0,
+            RwPolicy::WriterPreferred => state & (WRITER | WRITER_WAITING) == 0,
+            RwPolicy::Fair => {
+                state & WRITER == 0 && (state & WRITER_WAITING == 0 || state & READER_TURN != 0)
+            }
+        }
+    }
+
+    // With the Fair policy, readers kept out by a writer say so, to be let
+    // in before the next writer once it's done, and count themselves in
+    // `blocked` once until they're in.
+    #[inline]
+    fn reader_blocked(&self, state: usize, counted: &mut bool) {
+        if self.policy != RwPolicy::Fair || state & (WRITER | WRITER_WAITING) == 0 {
+            return;
+        }
+        if !*counted {
+            self.blocked.fetch_add(1, Relaxed);
+            *counted = true;
+        }
+        if state & READER_WAITING == 0 {
+            self.state.fetch_or(READER_WAITING, Relaxed);
+        }
+    }
+
+    // A reader counted by reader_blocked got in; the last one ends the
+    // readers' turn, writers may queue up again.
+    #[inline]
+    fn reader_admitted(&self, counted: bool) {
+        if counted && self.blocked.fetch_sub(1, Relaxed) == 1 {
+            self.state.fetch_and(!READER_TURN, Relaxed);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn read(&self) {
+        if !self.try_read_weak() {
+            self.read_slow();
+        }
+        self.acquired();
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn read_slow(&self) {
+        let mut backoff = Backoff::with_jitter();
+        #[cfg(feature = "deadlock_detection")]
+        let mut wait = crate::deadlock::Wait::new(self);
+        let mut counted = false;
+        loop {
+            loop {
+                let state = self.state.load(Relaxed);
+                if self.admits_reader(state) {
+                    break;
+                }
+                self.reader_blocked(state, &mut counted);
+                #[cfg(feature = "deadlock_detection")]
+                wait.tick();
+                backoff.relax();
+            }
+            if self.try_read_weak() {
+                self.reader_admitted(counted);
+                return;
+            }
+        }
+    }
+
+    #[inline]
+    fn try_read_weak(&self) -> bool {
+        let state = self.state.load(Relaxed);
+        self.admits_reader(state)
+            && self
+                .state
+                .compare_exchange_weak(state, state + READER, Acquire, Relaxed)
+                .is_ok()
+    }
+
+    // Only fails if a writer holds the lock; other readers coming and going
+    // while we try just mean another attempt.
+    #[inline]
+    pub(crate) fn try_read(&self) -> bool {
+        let mut state = self.state.load(Relaxed);
+        while self.admits_reader(state) {
+            match self
+                .state
+                .compare_exchange_weak(state, state + READER, Acquire, Relaxed)
+            {
+                Ok(_) => {
+                    self.acquired();
+                    return true;
+                }
+                Err(s) => state = s,
+            }
+        }
+        false
+    }
+
+    // Readers that may already hold a read lock get in whenever no writer
+    // holds it, ignoring waiting writers, since those wait for them.
+    #[inline]
+    pub(crate) fn read_recursive(&self) {
+        if !self.try_read_recursive() {
+            self.read_recursive_slow();
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn read_recursive_slow(&self) {
+        let mut backoff = Backoff::with_jitter();
+        #[cfg(feature = "deadlock_detection")]
+        let mut wait = crate::deadlock::Wait::new(self);
+        while !self.try_read_recursive() {
+            #[cfg(feature = "deadlock_detection")]
+            wait.tick();
+            backoff.relax();
+        }
+    }
+
+    #[inline]
+    pub(crate) fn try_read_recursive(&self) -> bool {
+        let mut state = self.state.load(Relaxed);
+        while state & WRITER == 0 {
+            match self
+                .state
+                .compare_exchange_weak(state, state + READER, Acquire, Relaxed)
+            {
+                Ok(_) => {
+                    self.acquired();
+                    return true;
+                }
+                Err(s) => state = s,
+            }
+        }
+        false
+    }
+
+    // Safety: the caller must hold a read lock
+    #[inline]
+    pub(crate) unsafe fn unlock_read(&self) {
+        self.released();
+        self.state.fetch_sub(READER, Release);
+    }
+
+    #[inline]
+    pub(crate) fn upgradable(&self) {
+        if !self.try_upgradable_weak() {
+            self.upgradable_slow();
+        }
+        self.acquired();
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn upgradable_slow(&self) {
+        let mut backoff = Backoff::with_jitter();
+        #[cfg(feature = "deadlock_detection")]
+        let mut wait = crate::deadlock::Wait::new(self);
+        let mut counted = false;
+        loop {
+            loop {
+                let state = self.state.load(Relaxed);
+                if self.admits_reader(state) && state & UPGRADABLE == 0 {
+                    break;
+                }
+                self.reader_blocked(state, &mut counted);
+                #[cfg(feature = "deadlock_detection")]
+                wait.tick();
+                backoff.relax();
+            }
+            if self.try_upgradable_weak() {
+                self.reader_admitted(counted);
+                return;
+            }
+        }
+    }
+
+    #[inline]
+    fn try_upgradable_weak(&self) -> bool {
+        let state = self.state.load(Relaxed);
+        self.admits_reader(state)
+            && state & UPGRADABLE == 0
+            && self
+                .state
+                .compare_exchange_weak(state, state | UPGRADABLE, Acquire, Relaxed)
+                .is_ok()
+    }
+
+    #[inline]
+    pub(crate) fn try_upgradable(&self) -> bool {
+        let mut state = self.state.load(Relaxed);
+        while self.admits_reader(state) && state & UPGRADABLE == 0 {
+            match self
+                .state
+                .compare_exchange_weak(state, state | UPGRADABLE, Acquire, Relaxed)
+            {
+                Ok(_) => {
+                    self.acquired();
+                    return true;
+                }
+                Err(s) => state = s,
+            }
+        }
+        false
+    }
+
+    // Safety: the caller must hold the upgradable lock
+    #[inline]
+    pub(crate) unsafe fn unlock_upgradable(&self) {
+        self.released();
+        self.state.fetch_and(!UPGRADABLE, Release);
+    }
+
+    // Safety: the caller must hold the upgradable lock, and holds the write
+    // lock instead once this returns
+    #[inline]
+    pub(crate) unsafe fn upgrade(&self) {
+        if !self.try_upgrade_weak() {
+            self.upgrade_slow();
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn upgrade_slow(&self) {
+        let mut backoff = Backoff::with_jitter();
+        while !self.try_upgrade_weak() {
+            backoff.relax();
+        }
+    }
+
+    // Waiting flags stay as they are, they're about whoever else is waiting.
+    // So does the readers' turn: the readers still to come in wait for the
+    // upgradable lock, and so for the upgrade.
+    #[inline]
+    fn try_upgrade_weak(&self) -> bool {
+        let state = self.state.load(Relaxed);
+        let waiting = state & (WRITER_WAITING | READER_WAITING | READER_TURN);
+        state & !waiting == UPGRADABLE
+            && self
+                .state
+                .compare_exchange_weak(state, waiting | WRITER, Acquire, Relaxed)
+                .is_ok()
+    }
+
+    // Safety: the caller must hold the upgradable lock, and holds the write
+    // lock instead if this returns true
+    #[inline]
+    pub(crate) unsafe fn try_upgrade(&self) -> bool {
+        let waiting = WRITER_WAITING | READER_WAITING | READER_TURN;
+        self.state
+            .fetch_update(Acquire, Relaxed, |state| {
+                (state & !waiting == UPGRADABLE).then_some((state & waiting) | WRITER)
+            })
+            .is_ok()
+    }
+
+    #[inline]
+    pub(crate) fn write(&self) {
+        if !self.try_write_weak() {
+            self.write_slow();
+        }
+        self.acquired();
+    }
+
+    // Free unless only waiting flags are set. Taking the lock clears the
+    // writer one, other waiting writers set it again; the reader one stays
+    // for the unlock to turn into the readers' turn.
+    #[inline]
+    fn try_write_weak(&self) -> bool {
+        let state = self.state.load(Relaxed);
+        state & !(WRITER_WAITING | READER_WAITING) == 0
+            && self
+                .state
+                .compare_exchange_weak(state, (state & READER_WAITING) | WRITER, Acquire, Relaxed)
+                .is_ok()
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn write_slow(&self) {
+        let mut backoff = Backoff::with_jitter();
+        #[cfg(feature = "deadlock_detection")]
+        let mut wait = crate::deadlock::Wait::new(self);
+        loop {
+            loop {
+                let state = self.state.load(Relaxed);
+                if state & !(WRITER_WAITING | READER_WAITING) == 0 {
+                    break;
+                }
+                self.end_stale_turn(state);
+                // keep new readers out, unless it's their turn
+                if self.policy != RwPolicy::ReaderPreferred
+                    && state & (WRITER_WAITING | READER_TURN) == 0
+                {
+                    self.state.fetch_or(WRITER_WAITING, Relaxed);
+                }
+                #[cfg(feature = "deadlock_detection")]
+                wait.tick();
+                backoff.relax();
+            }
+            if self.try_write_weak() {
+                return;
+            }
+        }
+    }
+
+    // A readers' turn with none of its readers still out ends here, e.g.
+    // one asked for by a reader that then got in without it.
+    #[inline]
+    fn end_stale_turn(&self, state: usize) {
+        if state & READER_TURN != 0 && self.blocked.load(Relaxed) == 0 {
+            self.state.fetch_and(!READER_TURN, Relaxed);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn try_write(&self) -> bool {
+        self.end_stale_turn(self.state.load(Relaxed));
+        let locked = self
+            .state
+            .fetch_update(Acquire, Relaxed, |state| {
+                (state & !(WRITER_WAITING | READER_WAITING) == 0)
+                    .then_some((state & READER_WAITING) | WRITER)
+            })
+            .is_ok();
+        if locked {
+            self.acquired();
+        }
+        locked
+    }
+
+    // Safety: the caller must hold the write lock
+    #[inline]
+    pub(crate) unsafe fn unlock_write(&self) {
+        self.released();
+        if self.policy == RwPolicy::Fair {
+            // hand over to the readers kept waiting, if any
+            let _ = self.state.fetch_update(Release, Relaxed, |state| {
+                let state = state & !WRITER;
+                Some(if state & READER_WAITING != 0 {
+                    (state & !(READER_WAITING | WRITER_WAITING)) | READER_TURN
+                } else {
+                    state
+                })
+            });
+        } else {
+            self.state.fetch_and(!WRITER, Release);
+        }
+    }
+
+    // Safety: the caller must hold the write lock, and holds a read lock
+    // instead once this returns
+    #[inline]
+    pub(crate) unsafe fn downgrade(&self) {
+        // readers and upgraders CAS against a clear writer bit, so the state is
+        // WRITER plus maybe waiting flags here, and this swaps the writer for
+        // a single reader
+        self.state.fetch_add(READER - WRITER, Release);
+    }
+
+    // Bookkeeping for deadlock detection. Upgrades and downgrades keep the
+    // same holder, so only taking and releasing the lock needs it.
+    #[inline]
+    fn acquired(&self) {
+        #[cfg(feature = "deadlock_detection")]
+        crate::deadlock::acquired(self as *const Self as usize);
+    }
+
+    #[inline]
+    fn released(&self) {
+        #[cfg(feature = "deadlock_detection")]
+        crate::deadlock::released(self as *const Self as usize);
+    }
+}
+
+// Timed variants of the try_* methods, false means they timed out.
+#[cfg(feature = "std")]
+impl RawRwSpinLock {
+    pub(crate) fn try_read_until(&self, deadline: Instant) -> bool {
+        self.until(deadline, || self.try_read())
+    }
+
+    pub(crate) fn try_upgradable_until(&self, deadline: Instant) -> bool {
+        self.until(deadline, || self.try_upgradable())
+    }
+
+    pub(crate) fn try_write_until(&self, deadline: Instant) -> bool {
+        self.until(deadline, || self.try_write())
+    }
+
+    fn until(&self, deadline: Instant, mut attempt: impl FnMut() -> bool) -> bool {
+        let mut backoff = Backoff::with_jitter();
+        #[cfg(feature = "deadlock_detection")]
+        let mut wait = crate::deadlock::Wait::new(self);
+        loop {
+            if attempt() {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            #[cfg(feature = "deadlock_detection")]
+            wait.tick();
+            backoff.relax();
+        }
+    }
+}
+
+impl Default for RawRwSpinLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct RwSpinLock<T> {
+    pub(crate) raw: RawRwSpinLock,
+    pub(crate) value: UnsafeCell<T>,
+}
+
+// Readers on different threads share &T at the same time, so T must be Sync as well as Send.
+unsafe impl<T> Sync for RwSpinLock<T> where T: Send + Sync {}
+
+impl<T> RwSpinLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self::with_policy(value, RwPolicy::ReaderPreferred)
+    }
+
+    /// A lock that resolves contention between readers and writers by
+    /// `policy` rather than always letting readers in, see `RwPolicy`.
+    pub const fn with_policy(value: T, policy: RwPolicy) -> Self {
+        Self {
+            raw: RawRwSpinLock::with_policy(policy),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline]
+    pub fn read(&self) -> ReadGuard<'_, T> {
+        self.raw.read();
+        ReadGuard { lock: self }
+    }
+
+    /// `read` for code that may already hold a read lock on this thread,
+    /// e.g. a read section calling back into another one. Under
+    /// `WriterPreferred` or `Fair`, a plain `read` there would wait for a
+    /// queued writer, which waits for the outer read guard: a deadlock. This
+    /// gets in whenever no writer actually holds the lock.
+    ///
+    /// It doesn't check that the thread holds a read lock, so used for
+    /// first acquisitions it lets readers cut in front of waiting writers
+    /// and can starve them.
+    ///
+    /// ```
+    /// use spinlock::{RwPolicy, RwSpinLock};
+    ///
+    /// let lock = RwSpinLock::with_policy(0, RwPolicy::WriterPreferred);
+    /// std::thread::scope(|s| {
+    ///     let outer = lock.read();
+    ///     s.spawn(|| *lock.write() += 1);
+    ///     // give the writer time to queue up behind `outer`
+    ///     std::thread::sleep(std::time::Duration::from_millis(10));
+    ///     assert_eq!(*lock.read_recursive(), 0);
+    ///     drop(outer);
+    /// });
+    /// assert_eq!(*lock.read(), 1);
+    /// ```
+    #[inline]
+    pub fn read_recursive(&self) -> ReadGuard<'_, T> {
+        self.raw.read_recursive();
+        ReadGuard { lock: self }
+    }
+
+    /// Shared access that can later be turned into exclusive access without
+    /// unlocking in between. Coexists with plain readers, but only one
+    /// upgradable reader (and no writer) can hold the lock at a time.
+    #[inline]
+    pub fn upgradeable_read(&self) -> UpgradableReadGuard<'_, T> {
+        self.raw.upgradable();
+        UpgradableReadGuard { lock: self }
+    }
+
+    #[inline]
+    pub fn write(&self) -> WriteGuard<'_, T> {
+        self.raw.write();
+        WriteGuard { lock: self }
+    }
+
+    // Single attempts, never spin. None means the lock is held in a
+    // conflicting mode.
+    #[inline]
+    pub fn try_read(&self) -> Option<ReadGuard<'_, T>> {
+        self.raw.try_read().then(|| ReadGuard { lock: self })
+    }
+
+    /// Single attempt of `read_recursive`.
+    #[inline]
+    pub fn try_read_recursive(&self) -> Option<ReadGuard<'_, T>> {
+        self.raw
+            .try_read_recursive()
+            .then(|| ReadGuard { lock: self })
+    }
+
+    #[inline]
+    pub fn try_upgradeable_read(&self) -> Option<UpgradableReadGuard<'_, T>> {
+        self.raw
+            .try_upgradable()
+            .then(|| UpgradableReadGuard { lock: self })
+    }
+
+    #[inline]
+    pub fn try_write(&self) -> Option<WriteGuard<'_, T>> {
+        self.raw.try_write().then(|| WriteGuard { lock: self })
+    }
+
+    // Snapshots of who holds the lock, for metrics and admission control.
+    // They may be stale by the time the caller looks, so don't synchronize
+    // on them.
+
+    /// Whether any guard, read or write, holds the lock.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.raw.is_locked()
+    }
+
+    #[inline]
+    pub fn is_write_locked(&self) -> bool {
+        self.raw.is_write_locked()
+    }
+
+    /// How many read guards hold the lock, counting an upgradable one.
+    ///
+    /// ```
+    /// let lock = spinlock::RwSpinLock::new(0);
+    /// let (_a, _b) = (lock.read(), lock.upgradeable_read());
+    /// assert_eq!(lock.reader_count(), 2);
+    /// assert!(!lock.is_write_locked());
+    /// ```
+    #[inline]
+    pub fn reader_count(&self) -> usize {
+        self.raw.reader_count()
+    }
+}
+
+/// Timed acquisition, for callers that would rather take a fallback path
+/// than wait out writer starvation. None means the deadline passed.
+#[cfg(feature = "std")]
+impl<T> RwSpinLock<T> {
+    pub fn try_read_for(&self, timeout: Duration) -> Option<ReadGuard<'_, T>> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.try_read_until(deadline),
+            // too far out to ever happen
+            None => Some(self.read()),
+        }
+    }
+
+    pub fn try_read_until(&self, deadline: Instant) -> Option<ReadGuard<'_, T>> {
+        self.raw
+            .try_read_until(deadline)
+            .then(|| ReadGuard { lock: self })
+    }
+
+    pub fn try_upgradeable_read_for(
+        &self,
+        timeout: Duration,
+    ) -> Option<UpgradableReadGuard<'_, T>> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.try_upgradeable_read_until(deadline),
+            // too far out to ever happen
+            None => Some(self.upgradeable_read()),
+        }
+    }
+
+    pub fn try_upgradeable_read_until(
+        &self,
+        deadline: Instant,
+    ) -> Option<UpgradableReadGuard<'_, T>> {
+        self.raw
+            .try_upgradable_until(deadline)
+            .then(|| UpgradableReadGuard { lock: self })
+    }
+
+    pub fn try_write_for(&self, timeout: Duration) -> Option<WriteGuard<'_, T>> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.try_write_until(deadline),
+            // too far out to ever happen
+            None => Some(self.write()),
+        }
+    }
+
+    pub fn try_write_until(&self, deadline: Instant) -> Option<WriteGuard<'_, T>> {
+        self.raw
+            .try_write_until(deadline)
+            .then(|| WriteGuard { lock: self })
+    }
+}
+
+pub struct ReadGuard<'a, T> {
+    pub(crate) lock: &'a RwSpinLock<T>,
+}
+
+impl<T> Deref for ReadGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: no writer can get in while this read guard is alive
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> AsRef<T> for ReadGuard<'_, T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T> Borrow<T> for ReadGuard<'_, T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for ReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T> Drop for ReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // safety: the guard is the proof we hold a read lock
+        unsafe { self.lock.raw.unlock_read() }
+    }
+}
+
+pub struct WriteGuard<'a, T> {
+    pub(crate) lock: &'a RwSpinLock<T>,
+}
+
+impl<'a, T> WriteGuard<'a, T> {
+    /// Turns exclusive access into shared access in one step, so no other
+    /// writer can get in between publishing an update and reading it back.
+    pub fn downgrade(self) -> ReadGuard<'a, T> {
+        let lock = self.lock;
+        mem::forget(self);
+        // safety: we held the write lock, now owned by the read guard
+        unsafe { lock.raw.downgrade() };
+        ReadGuard { lock }
+    }
+}
+
+impl<T> Deref for WriteGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: the writer bit excludes every other guard
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for WriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: the writer bit excludes every other guard
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> AsRef<T> for WriteGuard<'_, T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T> AsMut<T> for WriteGuard<'_, T> {
+    fn as_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T> Borrow<T> for WriteGuard<'_, T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for WriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for WriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T> Drop for WriteGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // safety: the guard is the proof we hold the write lock
+        unsafe { self.lock.raw.unlock_write() }
+    }
+}
+
+pub struct UpgradableReadGuard<'a, T> {
+    pub(crate) lock: &'a RwSpinLock<T>,
+}
+
+impl<'a, T> UpgradableReadGuard<'a, T> {
+    /// Waits for the remaining plain readers to leave and turns this guard
+    /// into a write guard. No other writer can get in between, since the
+    /// upgradable bit keeps them out the whole time.
+    pub fn upgrade(self) -> WriteGuard<'a, T> {
+        let lock = self.lock;
+        mem::forget(self);
+        // safety: we held the upgradable lock, now owned by the write guard
+        unsafe { lock.raw.upgrade() };
+        WriteGuard { lock }
+    }
+
+    /// Upgrades only if there are no plain readers right now, otherwise hands
+    /// the guard back unchanged.
+    pub fn try_upgrade(self) -> Result<WriteGuard<'a, T>, Self> {
+        // safety: the guard is the proof we hold the upgradable lock
+        if unsafe { self.lock.raw.try_upgrade() } {
+            let lock = self.lock;
+            mem::forget(self);
+            Ok(WriteGuard { lock })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<T> Deref for UpgradableReadGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: writers are excluded while the upgradable bit is set
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> AsRef<T> for UpgradableReadGuard<'_, T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T> Borrow<T> for UpgradableReadGuard<'_, T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for UpgradableReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for UpgradableReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T> Drop for UpgradableReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // safety: the guard is the proof we hold the upgradable lock
+        unsafe { self.lock.raw.unlock_upgradable() }
+    }
+}
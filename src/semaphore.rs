@@ -0,0 +1,79 @@
+use core::sync::atomic::AtomicUsize;
+
+use crate::sync::{Acquire, Relaxed, Release};
+use crate::Backoff;
+
+/// Counting semaphore: at most `permits` holders at a time.
+pub struct SpinSemaphore {
+    permits: AtomicUsize,
+}
+
+impl SpinSemaphore {
+    pub const fn new(permits: usize) -> Self {
+        Self {
+            permits: AtomicUsize::new(permits),
+        }
+    }
+
+    #[inline]
+    pub fn acquire(&self) -> Permit<'_> {
+        if !self.try_acquire_weak() {
+            self.acquire_slow();
+        }
+        Permit { semaphore: self }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn acquire_slow(&self) {
+        let mut backoff = Backoff::with_jitter();
+        loop {
+            while self.permits.load(Relaxed) == 0 {
+                backoff.relax();
+            }
+            if self.try_acquire_weak() {
+                return;
+            }
+        }
+    }
+
+    #[inline]
+    fn try_acquire_weak(&self) -> bool {
+        let permits = self.permits.load(Relaxed);
+        permits != 0
+            && self
+                .permits
+                .compare_exchange_weak(permits, permits - 1, Acquire, Relaxed)
+                .is_ok()
+    }
+
+    // Only fails if there are no permits left; other threads taking and
+    // returning permits while we try just mean another attempt.
+    #[inline]
+    pub fn try_acquire(&self) -> Option<Permit<'_>> {
+        self.permits
+            .fetch_update(Acquire, Relaxed, |p| p.checked_sub(1))
+            .ok()
+            .map(|_| Permit { semaphore: self })
+    }
+
+    /// Adds `n` permits. Permits from `acquire` go back on their own when
+    /// dropped, so this is for growing the pool or returning permits that
+    /// were `forget`-ed.
+    #[inline]
+    pub fn release(&self, n: usize) {
+        self.permits.fetch_add(n, Release);
+    }
+}
+
+/// One permit from a `SpinSemaphore`, handed back when dropped.
+pub struct Permit<'a> {
+    semaphore: &'a SpinSemaphore,
+}
+
+impl Drop for Permit<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.semaphore.release(1);
+    }
+}
@@ -0,0 +1,78 @@
+use core::sync::atomic::{fence, AtomicUsize};
+
+use crate::sync::{Acquire, Relaxed, Release};
+use crate::Backoff;
+
+/// The bare sequence counter behind `SeqLock`, for protecting data laid out
+/// however the caller likes. Readers bracket their reads with `read_seq`
+/// and `validate` and retry on failure; writers bracket their writes with
+/// `begin_write` and `end_write`.
+///
+/// The counter doesn't exclude writers from each other, so writers have to
+/// be serialized some other way, e.g. by a lock. Readers must also cope with
+/// seeing torn data before `validate` tells them to throw it away, which in
+/// practice means reading through atomics or volatile copies.
+pub struct SeqCount {
+    // odd while a write is in progress
+    pub(crate) seq: AtomicUsize,
+}
+
+impl SeqCount {
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicUsize::new(0),
+        }
+    }
+
+    /// Starts a read, waiting out any write in progress. Pass the result to
+    /// `validate` once done reading.
+    #[inline]
+    pub fn read_seq(&self) -> usize {
+        let seq = self.seq.load(Acquire);
+        if seq & 1 == 0 {
+            seq
+        } else {
+            self.read_seq_slow()
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn read_seq_slow(&self) -> usize {
+        let mut backoff = Backoff::with_jitter();
+        loop {
+            let seq = self.seq.load(Acquire);
+            if seq & 1 == 0 {
+                return seq;
+            }
+            backoff.relax();
+        }
+    }
+
+    /// Whether no write started since `read_seq` returned `seq`, i.e.
+    /// whether what was read in between is consistent.
+    #[inline]
+    pub fn validate(&self, seq: usize) -> bool {
+        // keeps the reads being validated from moving after the check
+        fence(Acquire);
+        self.seq.load(Relaxed) == seq
+    }
+
+    #[inline]
+    pub fn begin_write(&self) {
+        self.seq.fetch_add(1, Relaxed);
+        // readers that see any of the new data must see the odd count first
+        fence(Release);
+    }
+
+    #[inline]
+    pub fn end_write(&self) {
+        self.seq.fetch_add(1, Release);
+    }
+}
+
+impl Default for SeqCount {
+    fn default() -> Self {
+        Self::new()
+    }
+}
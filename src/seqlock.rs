@@ -0,0 +1,142 @@
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+
+use crate::sync::Acquire;
+use crate::{Backoff, RawSpinLock, SeqCount};
+
+/// Lock for small `Copy` data with many readers. Readers never write to the
+/// lock; they copy the value out and retry if a writer got in meanwhile.
+/// Writers exclude each other with a spinlock.
+pub struct SeqLock<T: Copy> {
+    seq: SeqCount,
+    lock: RawSpinLock,
+    value: UnsafeCell<T>,
+}
+
+// Readers only ever get copies, so T doesn't need to be Sync.
+unsafe impl<T: Copy + Send> Sync for SeqLock<T> {}
+
+impl<T: Copy> SeqLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            seq: SeqCount::new(),
+            lock: RawSpinLock::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// A consistent snapshot of the value. Spins while a write is in
+    /// progress, so writers should keep their critical sections short.
+    #[inline]
+    pub fn read(&self) -> T {
+        match self.try_read() {
+            Some(value) => value,
+            None => self.read_slow(),
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn read_slow(&self) -> T {
+        let mut backoff = Backoff::with_jitter();
+        loop {
+            backoff.relax();
+            if let Some(value) = self.try_read() {
+                return value;
+            }
+        }
+    }
+
+    /// A single optimistic read; None if a writer was active during it.
+    #[inline]
+    pub fn try_read(&self) -> Option<T> {
+        let seq = self.seq.seq.load(Acquire);
+        if seq & 1 != 0 {
+            return None;
+        }
+        // safety: the copy may race with a writer and come out torn, which is
+        // why it stays a MaybeUninit until the sequence check says it isn't
+        let value = unsafe { ptr::read_volatile(self.value.get() as *const MaybeUninit<T>) };
+        if self.seq.validate(seq) {
+            // safety: no writer touched the value while we copied it
+            Some(unsafe { value.assume_init() })
+        } else {
+            None
+        }
+    }
+
+    /// Exclusive access for updating the value in place. Readers retry until
+    /// the guard is dropped.
+    #[inline]
+    pub fn write(&self) -> SeqLockGuard<'_, T> {
+        self.lock.lock();
+        self.seq.begin_write();
+        SeqLockGuard {
+            lock: self,
+            marker: PhantomData,
+        }
+    }
+
+    /// Replaces the value.
+    #[inline]
+    pub fn set(&self, value: T) {
+        *self.write() = value;
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T: Copy + Default> Default for SeqLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Copy> From<T> for SeqLock<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+pub struct SeqLockGuard<'a, T: Copy> {
+    lock: &'a SeqLock<T>,
+    // Send and Sync are implemented below rather than following `lock`
+    marker: PhantomData<*const ()>,
+}
+
+unsafe impl<T: Copy + Send> Send for SeqLockGuard<'_, T> {}
+unsafe impl<T: Copy + Sync> Sync for SeqLockGuard<'_, T> {}
+
+impl<T: Copy> Deref for SeqLockGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: the write lock excludes other writers, and readers only copy
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: Copy> DerefMut for SeqLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: the write lock excludes other writers, and readers throw
+        // away anything they copied while the sequence was odd
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T: Copy> Drop for SeqLockGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.seq.end_write();
+        // safety: the guard is the proof we hold the write lock
+        unsafe { self.lock.lock.unlock() }
+    }
+}
@@ -0,0 +1,44 @@
+//! `serde` support, with the `serde` feature: a lock serializes as the value
+//! it protects, taking the lock (for reading, on the RW lock) while doing so,
+//! and deserializes into a new unlocked lock around the value.
+//!
+//! ```
+//! # #[cfg(feature = "serde")] {
+//! use spinlock::SpinLock;
+//!
+//! let lock = SpinLock::new(vec![1, 2]);
+//! let json = serde_json::to_string(&lock).unwrap();
+//! assert_eq!(json, "[1,2]");
+//! let lock: SpinLock<Vec<i32>> = serde_json::from_str(&json).unwrap();
+//! assert_eq!(*lock.lock(), [1, 2]);
+//! # }
+//! ```
+
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Relax, RwSpinLock, SpinLock};
+
+impl<T: ?Sized + Serialize, R: Relax> Serialize for SpinLock<T, R> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.lock().serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, R: Relax> Deserialize<'de> for SpinLock<T, R> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(SpinLock::with_relax)
+    }
+}
+
+impl<T: Serialize> Serialize for RwSpinLock<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.read().serialize(serializer)
+    }
+}
+
+// The policy isn't part of the data, the lock comes back with the default.
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for RwSpinLock<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(RwSpinLock::new)
+    }
+}
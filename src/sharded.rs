@@ -0,0 +1,60 @@
+use core::array;
+
+use crate::{CachePadded, SpinLock};
+
+/// `N` independently locked shards of `T`, each on its own cache line, so
+/// that threads working on different keys don't contend. The usual building
+/// block for a hot shared map or counter: put a map or a counter in every
+/// shard and pick the shard from the key's hash.
+pub struct ShardedSpinLock<T, const N: usize> {
+    shards: [CachePadded<SpinLock<T>>; N],
+}
+
+impl<T, const N: usize> ShardedSpinLock<T, N> {
+    /// Builds shard `i` with `f(i)`. `N` must not be 0.
+    pub fn from_fn<F: FnMut(usize) -> T>(mut f: F) -> Self {
+        const { assert!(N > 0, "a ShardedSpinLock needs at least one shard") };
+        Self {
+            shards: array::from_fn(|i| CachePadded::new(SpinLock::new(f(i)))),
+        }
+    }
+
+    /// The shard for a precomputed hash.
+    #[inline]
+    pub fn shard(&self, hash: u64) -> &SpinLock<T> {
+        &self.shards[(hash % N as u64) as usize]
+    }
+
+    /// Every shard in order, for maintenance like clearing or summing up.
+    /// Each one is locked separately, so this is no snapshot across shards.
+    pub fn iter_shards(&self) -> impl Iterator<Item = &SpinLock<T>> {
+        self.shards.iter().map(|shard| &**shard)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.shards.get_mut(index).map(|shard| shard.get_mut())
+    }
+
+    pub fn into_inner(self) -> [T; N] {
+        self.shards.map(|shard| shard.into_inner().into_inner())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, const N: usize> ShardedSpinLock<T, N> {
+    /// The shard `key` belongs to. The hash is fixed, the same key always
+    /// gives the same shard.
+    pub fn get<K: core::hash::Hash + ?Sized>(&self, key: &K) -> &SpinLock<T> {
+        use core::hash::Hasher;
+
+        let mut hasher = std::hash::DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.shard(hasher.finish())
+    }
+}
+
+impl<T: Default, const N: usize> Default for ShardedSpinLock<T, N> {
+    fn default() -> Self {
+        Self::from_fn(|_| T::default())
+    }
+}
@@ -0,0 +1,176 @@
+//! Wait-free single-producer single-consumer ring buffer, for handing
+//! values down a pipeline of threads. `try_push` and `try_pop` never wait;
+//! `push` and `pop` spin with a `Relax` strategy while the buffer is full or
+//! empty.
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, AtomicUsize};
+
+use crate::sync::{Acquire, Relaxed, Release};
+use crate::{Backoff, CachePadded, Relax};
+
+/// A buffer for up to `capacity` values. Panics if `capacity` is 0.
+pub fn channel<T>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    channel_with_relax(capacity)
+}
+
+/// `channel` for a non-default relax strategy in `push` and `pop`, e.g.
+/// `spsc::channel_with_relax::<_, Yield>(64)`.
+pub fn channel_with_relax<T, R: Relax>(capacity: usize) -> (Producer<T, R>, Consumer<T, R>) {
+    assert!(
+        capacity > 0,
+        "an spsc channel needs room for at least one value"
+    );
+    let ring = Arc::new(Ring {
+        slots: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        head: CachePadded::new(AtomicUsize::new(0)),
+        tail: CachePadded::new(AtomicUsize::new(0)),
+        closed: AtomicBool::new(false),
+    });
+    (
+        Producer {
+            ring: ring.clone(),
+            relax: PhantomData,
+        },
+        Consumer {
+            ring,
+            relax: PhantomData,
+        },
+    )
+}
+
+struct Ring<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    // Positions in 0..2 * capacity, so a full buffer (tail - head ==
+    // capacity) can be told apart from an empty one. Only the consumer
+    // writes head and only the producer writes tail.
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+    // set by whichever side is dropped first
+    closed: AtomicBool,
+}
+
+// Each slot is only ever accessed by one side at a time, handed over by the
+// head and tail stores.
+unsafe impl<T: Send> Sync for Ring<T> {}
+
+impl<T> Ring<T> {
+    fn slot(&self, pos: usize) -> *mut MaybeUninit<T> {
+        self.slots[pos % self.slots.len()].get()
+    }
+
+    fn next(&self, pos: usize) -> usize {
+        if pos + 1 == 2 * self.slots.len() {
+            0
+        } else {
+            pos + 1
+        }
+    }
+
+    fn len(&self, head: usize, tail: usize) -> usize {
+        (tail + 2 * self.slots.len() - head) % (2 * self.slots.len())
+    }
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        let (mut head, tail) = (*self.head.get_mut(), *self.tail.get_mut());
+        while head != tail {
+            // safety: the values the consumer never took
+            unsafe { (*self.slot(head)).assume_init_drop() };
+            head = self.next(head);
+        }
+    }
+}
+
+/// The sending half of an spsc `channel`.
+pub struct Producer<T, R = Backoff> {
+    ring: Arc<Ring<T>>,
+    relax: PhantomData<fn() -> R>,
+}
+
+impl<T, R: Relax> Producer<T, R> {
+    /// Adds `value`, or hands it back if the buffer is full.
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        let tail = self.ring.tail.load(Relaxed);
+        // Acquire: the consumer is done reading the slot it gave back
+        if self.ring.len(self.ring.head.load(Acquire), tail) == self.ring.slots.len() {
+            return Err(value);
+        }
+        // safety: the slot is outside head..tail, the consumer won't touch it
+        unsafe { (*self.ring.slot(tail)).write(value) };
+        self.ring.tail.store(self.ring.next(tail), Release);
+        Ok(())
+    }
+
+    /// Adds `value`, spinning while the buffer is full. Hands it back if the
+    /// consumer is gone.
+    pub fn push(&mut self, mut value: T) -> Result<(), T> {
+        let mut relax = R::default();
+        loop {
+            match self.try_push(value) {
+                Ok(()) => return Ok(()),
+                Err(v) => value = v,
+            }
+            if self.ring.closed.load(Relaxed) {
+                return Err(value);
+            }
+            relax.relax();
+        }
+    }
+}
+
+impl<T, R> Drop for Producer<T, R> {
+    fn drop(&mut self) {
+        self.ring.closed.store(true, Release);
+    }
+}
+
+/// The receiving half of an spsc `channel`.
+pub struct Consumer<T, R = Backoff> {
+    ring: Arc<Ring<T>>,
+    relax: PhantomData<fn() -> R>,
+}
+
+impl<T, R: Relax> Consumer<T, R> {
+    pub fn try_pop(&mut self) -> Option<T> {
+        let head = self.ring.head.load(Relaxed);
+        // Acquire: the producer is done writing the slot it handed over
+        if head == self.ring.tail.load(Acquire) {
+            return None;
+        }
+        // safety: the slot is inside head..tail, the producer won't touch it
+        let value = unsafe { (*self.ring.slot(head)).assume_init_read() };
+        self.ring.head.store(self.ring.next(head), Release);
+        Some(value)
+    }
+
+    /// Takes the oldest value, spinning while the buffer is empty. None once
+    /// the producer is gone and everything it pushed has been taken.
+    pub fn pop(&mut self) -> Option<T> {
+        let mut relax = R::default();
+        loop {
+            if let Some(value) = self.try_pop() {
+                return Some(value);
+            }
+            // Acquire: everything pushed before the producer went away is
+            // visible to the last try_pop
+            if self.ring.closed.load(Acquire) {
+                return self.try_pop();
+            }
+            relax.relax();
+        }
+    }
+}
+
+impl<T, R> Drop for Consumer<T, R> {
+    fn drop(&mut self) {
+        self.ring.closed.store(true, Release);
+    }
+}
@@ -0,0 +1,70 @@
+use core::sync::atomic::AtomicU64;
+use core::time::Duration;
+
+use crate::sync::Relaxed;
+
+/// Contention counters for one lock, from `SpinLock::stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LockStats {
+    pub acquisitions: u64,
+    /// `try_lock` calls that found the lock held.
+    pub try_lock_failures: u64,
+    /// Relax rounds spent waiting in `lock`, summed over all waiters.
+    pub spins: u64,
+    pub total_hold: Duration,
+    pub max_hold: Duration,
+}
+
+pub(crate) struct Counters {
+    acquisitions: AtomicU64,
+    try_lock_failures: AtomicU64,
+    spins: AtomicU64,
+    total_hold: AtomicU64,
+    max_hold: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) const fn new() -> Self {
+        Self {
+            acquisitions: AtomicU64::new(0),
+            try_lock_failures: AtomicU64::new(0),
+            spins: AtomicU64::new(0),
+            total_hold: AtomicU64::new(0),
+            max_hold: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn acquired(&self) {
+        self.acquisitions.fetch_add(1, Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn released(&self, held: Duration) {
+        let held = held.as_nanos() as u64;
+        self.total_hold.fetch_add(held, Relaxed);
+        self.max_hold.fetch_max(held, Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn try_lock_failed(&self) {
+        self.try_lock_failures.fetch_add(1, Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn spun(&self, spins: u64) {
+        self.spins.fetch_add(spins, Relaxed);
+    }
+
+    // Each counter is read separately, so under contention the snapshot can
+    // be slightly inconsistent.
+    pub(crate) fn snapshot(&self) -> LockStats {
+        LockStats {
+            acquisitions: self.acquisitions.load(Relaxed),
+            try_lock_failures: self.try_lock_failures.load(Relaxed),
+            spins: self.spins.load(Relaxed),
+            total_hold: Duration::from_nanos(self.total_hold.load(Relaxed)),
+            max_hold: Duration::from_nanos(self.max_hold.load(Relaxed)),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// What the spinning locks are built from, swapped for a model checker's
+// versions under `--cfg loom` or `--cfg shuttle`, so it can explore how the
+// threads interleave, see tests/loom.rs and tests/shuttle.rs. The protected
+// values stay plain UnsafeCells, the loom tests put loom's in there to have
+// accesses checked.
+
+#[cfg(not(any(loom, shuttle)))]
+pub(crate) use core::hint::spin_loop;
+#[cfg(not(any(loom, shuttle)))]
+pub(crate) use core::sync::atomic::{fence, AtomicUsize};
+#[cfg(not(shuttle))]
+pub(crate) use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32};
+#[cfg(all(feature = "std", not(any(loom, shuttle))))]
+pub(crate) use std::thread::{sleep, yield_now};
+
+// The weaker orderings the crate uses its atomics with. With the
+// `strict-ordering` feature they're all SeqCst, fences included, so when
+// something looks like a missing barrier (say, only on Arm) a rebuild shows
+// whether the crate's orderings are to blame.
+#[cfg(not(feature = "strict-ordering"))]
+pub(crate) use core::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release};
+#[cfg(feature = "strict-ordering")]
+pub(crate) use strict::{AcqRel, Acquire, Relaxed, Release};
+
+#[cfg(feature = "strict-ordering")]
+#[allow(non_upper_case_globals)]
+mod strict {
+    use core::sync::atomic::Ordering::{self, SeqCst};
+
+    pub(crate) const Relaxed: Ordering = SeqCst;
+    pub(crate) const Acquire: Ordering = SeqCst;
+    pub(crate) const Release: Ordering = SeqCst;
+    pub(crate) const AcqRel: Ordering = SeqCst;
+}
+
+// Loom only models the usize atomics; other widths, flags and pointers stay
+// the core ones.
+#[cfg(loom)]
+pub(crate) use loom::hint::spin_loop;
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::fence;
+#[cfg(loom)]
+pub(crate) use loom::thread::yield_now;
+
+#[cfg(shuttle)]
+pub(crate) use shuttle::hint::spin_loop;
+#[cfg(shuttle)]
+pub(crate) use shuttle::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU32, AtomicUsize};
+#[cfg(shuttle)]
+pub(crate) use shuttle::thread::yield_now;
+
+// A real sleep would stall the model checker's schedule, to it a sleep is
+// just a chance for the other threads to run.
+#[cfg(all(feature = "std", any(loom, shuttle)))]
+pub(crate) fn sleep(_: std::time::Duration) {
+    yield_now()
+}
+
+// Loom's atomics can't be created in a const fn, so this one creates its
+// loom atomic on first use instead. Loom sees that as a write by whichever
+// thread gets there first, so a lock has to be used once inside the model
+// before it's shared with other threads, which also rules out statics.
+#[cfg(loom)]
+pub(crate) struct AtomicUsize {
+    initial: usize,
+    atomic: std::sync::OnceLock<loom::sync::atomic::AtomicUsize>,
+}
+
+#[cfg(loom)]
+macro_rules! forward {
+    ($($name:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {
+        $(
+            pub(crate) fn $name(&self, $($arg: $ty),*) -> $ret {
+                self.get().$name($($arg),*)
+            }
+        )*
+    };
+}
+
+#[cfg(loom)]
+impl AtomicUsize {
+    pub(crate) const fn new(value: usize) -> Self {
+        Self {
+            initial: value,
+            atomic: std::sync::OnceLock::new(),
+        }
+    }
+
+    fn get(&self) -> &loom::sync::atomic::AtomicUsize {
+        self.atomic
+            .get_or_init(|| loom::sync::atomic::AtomicUsize::new(self.initial))
+    }
+
+    // Loom lets a later read-modify-write read from before a plain store,
+    // even one the same thread made: a waiter's failed fetch_or racing with
+    // unlock's store could leave the lock looking taken forever, which no
+    // real machine does. A swap keeps the history in order.
+    pub(crate) fn store(&self, value: usize, order: core::sync::atomic::Ordering) {
+        self.get().swap(value, order);
+    }
+
+    pub(crate) fn fetch_update<F>(
+        &self,
+        set: core::sync::atomic::Ordering,
+        fetch: core::sync::atomic::Ordering,
+        f: F,
+    ) -> Result<usize, usize>
+    where
+        F: FnMut(usize) -> Option<usize>,
+    {
+        self.get().fetch_update(set, fetch, f)
+    }
+
+    forward! {
+        load(order: core::sync::atomic::Ordering) -> usize;
+        fetch_add(value: usize, order: core::sync::atomic::Ordering) -> usize;
+        fetch_sub(value: usize, order: core::sync::atomic::Ordering) -> usize;
+        fetch_and(value: usize, order: core::sync::atomic::Ordering) -> usize;
+        fetch_or(value: usize, order: core::sync::atomic::Ordering) -> usize;
+        compare_exchange(
+            current: usize,
+            new: usize,
+            success: core::sync::atomic::Ordering,
+            failure: core::sync::atomic::Ordering
+        ) -> Result<usize, usize>;
+        compare_exchange_weak(
+            current: usize,
+            new: usize,
+            success: core::sync::atomic::Ordering,
+            failure: core::sync::atomic::Ordering
+        ) -> Result<usize, usize>;
+    }
+}
@@ -0,0 +1,18 @@
+use core::sync::atomic::AtomicUsize;
+
+use crate::sync::Relaxed;
+
+// Handed out once per thread and never reused, unlike a thread-local's
+// address, so a lock still marked as owned by an exited thread can't be
+// mistaken for one owned by a new thread.
+static NEXT: AtomicUsize = AtomicUsize::new(1);
+
+std::thread_local! {
+    static ID: usize = NEXT.fetch_add(1, Relaxed);
+}
+
+/// A nonzero number identifying the calling thread, so 0 can mean "nobody".
+#[inline]
+pub(crate) fn current() -> usize {
+    ID.with(|id| *id)
+}
@@ -0,0 +1,140 @@
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+use crate::sync::{spin_loop, Acquire, AtomicUsize, Relaxed, Release};
+use crate::Backoff;
+
+/// FIFO spinlock: every thread draws a ticket and waits for its number to be
+/// served, so the lock is handed out strictly in arrival order.
+pub struct TicketLock<T> {
+    pub(crate) next_ticket: AtomicUsize,
+    pub(crate) now_serving: AtomicUsize,
+    pub(crate) value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for TicketLock<T> where T: Send {}
+
+impl<T> TicketLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            next_ticket: AtomicUsize::new(0),
+            now_serving: AtomicUsize::new(0),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline]
+    pub fn lock(&self) -> TicketGuard<'_, T> {
+        self.acquire();
+        TicketGuard {
+            lock: self,
+            marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn acquire(&self) {
+        let ticket = self.next_ticket.fetch_add(1, Relaxed);
+        if self.now_serving.load(Acquire) != ticket {
+            self.lock_slow(ticket);
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn lock_slow(&self, ticket: usize) {
+        let mut backoff = Backoff::new();
+        loop {
+            let serving = self.now_serving.load(Acquire);
+            if serving == ticket {
+                return;
+            }
+            // Wait roughly in proportion to how many threads are still ahead
+            // of us, yielding and then sleeping like `Backoff::relax` once it
+            // drags on, in case whoever is being served got descheduled.
+            if !backoff.escalate() {
+                for _ in 0..ticket.wrapping_sub(serving) {
+                    spin_loop();
+                }
+            }
+        }
+    }
+
+    // Only takes a ticket if it would be served immediately, so a failed
+    // attempt never leaves a hole in the queue.
+    #[inline]
+    pub fn try_lock(&self) -> Option<TicketGuard<'_, T>> {
+        let serving = self.now_serving.load(Acquire);
+        self.next_ticket
+            .compare_exchange(serving, serving.wrapping_add(1), Acquire, Relaxed)
+            .ok()
+            .map(|_| TicketGuard {
+                lock: self,
+                marker: PhantomData,
+            })
+    }
+}
+
+impl<T> TicketLock<T> {
+    // Safety: the caller must hold the lock
+    #[inline]
+    unsafe fn release(&self) {
+        // only the guard holder ever advances now_serving, no RMW needed
+        let serving = self.now_serving.load(Relaxed);
+        self.now_serving.store(serving.wrapping_add(1), Release);
+    }
+}
+
+pub struct TicketGuard<'a, T> {
+    pub(crate) lock: &'a TicketLock<T>,
+    // Send and Sync are implemented below rather than following `lock`
+    pub(crate) marker: PhantomData<*const ()>,
+}
+
+// Like `Guard`: any thread can serve the next ticket.
+unsafe impl<T: Send> Send for TicketGuard<'_, T> {}
+unsafe impl<T: Sync> Sync for TicketGuard<'_, T> {}
+
+impl<T> TicketGuard<'_, T> {
+    /// Unlocks, handing the lock straight to the next ticket in line. That's
+    /// what dropping the guard does too: tickets are always served in order,
+    /// and `try_lock` only succeeds when nobody is queued, so no thread can
+    /// barge in ahead of a waiter.
+    pub fn unlock_fair(self) {
+        drop(self)
+    }
+
+    /// Serves the next ticket and draws a new one at the back of the queue,
+    /// so a long loop holding the lock can let the others in. An associated
+    /// function so it doesn't shadow a `bump` on `T`.
+    pub fn bump(this: &mut Self) {
+        // safety: the guard is the proof we hold the lock, and it keeps not
+        // being used until our new ticket is served
+        unsafe { this.lock.release() };
+        this.lock.acquire();
+    }
+}
+
+impl<T> Deref for TicketGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // safety: only the holder of the ticket being served has a guard
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for TicketGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // safety: only the holder of the ticket being served has a guard
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for TicketGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // safety: the guard is the proof we hold the lock
+        unsafe { self.lock.release() }
+    }
+}
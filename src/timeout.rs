@@ -0,0 +1,60 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// Runs `future` until `sleep` completes, for any timer: pass the runtime's
+/// sleep future, e.g. `spinlock::timeout(lock.lock(), tokio::time::sleep(d))`.
+/// None means `sleep` finished first.
+///
+/// As soon as the time is up, `future` is dropped, so a lock future gives up
+/// its place in the queue (and any lock or permits it was just handed) then,
+/// rather than when the `Timeout` itself goes away. `future` is polled first,
+/// so one that's ready wins over a deadline that has already passed.
+///
+/// ```
+/// use spinlock::AsyncMutex;
+///
+/// let lock = AsyncMutex::new(0);
+/// let _held = lock.try_lock().unwrap();
+/// futures::executor::block_on(async {
+///     // an already elapsed deadline
+///     assert!(lock.lock_timeout(async {}).await.is_none());
+/// });
+/// ```
+pub fn timeout<F: Future, S: Future>(future: F, sleep: S) -> Timeout<F, S> {
+    Timeout {
+        future: Some(future),
+        sleep,
+    }
+}
+
+/// Future returned by `timeout`.
+pub struct Timeout<F, S> {
+    // None once it timed out
+    future: Option<F>,
+    sleep: S,
+}
+
+impl<F: Future, S: Future> Future for Timeout<F, S> {
+    type Output = Option<F::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // safety: neither field is ever moved out of, only dropped in place
+        let this = unsafe { self.get_unchecked_mut() };
+        let Some(future) = &mut this.future else {
+            return Poll::Ready(None);
+        };
+        // safety: as above, pinned along with `self`
+        if let Poll::Ready(output) = unsafe { Pin::new_unchecked(future) }.poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        // safety: as above
+        match unsafe { Pin::new_unchecked(&mut this.sleep) }.poll(cx) {
+            Poll::Ready(_) => {
+                this.future = None;
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
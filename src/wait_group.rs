@@ -0,0 +1,55 @@
+use core::sync::atomic::AtomicUsize;
+
+use crate::sync::{Acquire, Release};
+use crate::Backoff;
+
+/// Waits for a changing set of tasks to finish, like Go's `sync.WaitGroup`:
+/// `add` before starting tasks, `done` from each task once it's finished,
+/// and `wait` until the count drops back to zero.
+pub struct WaitGroup {
+    count: AtomicUsize,
+}
+
+impl WaitGroup {
+    pub const fn new() -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Adds `n` tasks to wait for. Panics if the count overflows.
+    pub fn add(&self, n: usize) {
+        self.count
+            .fetch_update(Release, Acquire, |count| count.checked_add(n))
+            .expect("WaitGroup count overflow");
+    }
+
+    /// Marks one task as finished. Panics if there are none left, i.e. more
+    /// `done` than `add`ed.
+    pub fn done(&self) {
+        self.count
+            .fetch_update(Release, Acquire, |count| count.checked_sub(1))
+            .expect("WaitGroup::done called more often than tasks were added");
+    }
+
+    /// Blocks until every task added so far is done, spinning at first and
+    /// then yielding to the scheduler. Everything the tasks did before their
+    /// `done` is visible afterwards.
+    pub fn wait(&self) {
+        let mut backoff = Backoff::with_jitter();
+        while self.count.load(Acquire) != 0 {
+            backoff.snooze();
+        }
+    }
+
+    /// Tasks added and not done yet. Only a snapshot.
+    pub fn count(&self) -> usize {
+        self.count.load(Acquire)
+    }
+}
+
+impl Default for WaitGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
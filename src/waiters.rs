@@ -0,0 +1,76 @@
+use alloc::collections::VecDeque;
+use core::task::Waker;
+
+// FIFO of wakers for the async primitives. Each pending future owns a key
+// (`None` until it first registers), so being polled again only refreshes its
+// waker instead of queueing it twice.
+pub(crate) struct WaitQueue {
+    waiters: VecDeque<(usize, Waker)>,
+    next_id: usize,
+}
+
+impl WaitQueue {
+    pub(crate) const fn new() -> Self {
+        Self {
+            waiters: VecDeque::new(),
+            next_id: 0,
+        }
+    }
+
+    pub(crate) fn register(&mut self, key: &mut Option<usize>, waker: &Waker) {
+        if let Some(id) = *key {
+            if let Some((_, w)) = self.waiters.iter_mut().find(|(i, _)| *i == id) {
+                if !w.will_wake(waker) {
+                    *w = waker.clone();
+                }
+                return;
+            }
+            // We were woken but lost the race for the resource, so we've
+            // already waited our turn: go back to the front.
+            self.waiters.push_front((id, waker.clone()));
+            return;
+        }
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.waiters.push_back((id, waker.clone()));
+        *key = Some(id);
+    }
+
+    pub(crate) fn contains(&self, id: usize) -> bool {
+        self.waiters.iter().any(|(i, _)| *i == id)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.waiters.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.waiters.is_empty()
+    }
+
+    // Drops a waiter that no longer needs waking. Returns false if it had
+    // already been popped, i.e. it was woken.
+    pub(crate) fn remove(&mut self, id: usize) -> bool {
+        match self.waiters.iter().position(|(i, _)| *i == id) {
+            Some(pos) => {
+                self.waiters.remove(pos);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Takes the next waiter off the queue. The caller wakes it after letting go
+    // of whatever protects the queue, so wakers never run under our lock.
+    pub(crate) fn pop(&mut self) -> Option<Waker> {
+        self.waiters.pop_front().map(|(_, w)| w)
+    }
+
+    // Empties the queue in one go, for waking everybody once unlocked.
+    pub(crate) fn take(&mut self) -> Self {
+        Self {
+            waiters: core::mem::take(&mut self.waiters),
+            next_id: self.next_id,
+        }
+    }
+}
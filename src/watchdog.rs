@@ -0,0 +1,100 @@
+//! Reports of locks held for too long, with the `watchdog` feature.
+//!
+//! Every `RawSpinLock` (and so `SpinLock` and the locks built on it) checks
+//! at unlock how long it was held. Anything over the threshold is printed to
+//! stderr, or passed to the hook if one is set, e.g. to fail a CI run:
+//!
+//! ```no_run
+//! # #[cfg(feature = "watchdog")] {
+//! use std::time::Duration;
+//!
+//! spinlock::watchdog::set_threshold(Duration::from_micros(100));
+//! spinlock::watchdog::set_hook(|hold| panic!("{hold}"));
+//! # }
+//! ```
+//!
+//! Reports are made after the lock is released, so the hook may take the
+//! same lock again.
+
+use core::fmt;
+use core::sync::atomic::AtomicU64;
+use core::time::Duration;
+use std::boxed::Box;
+use std::sync::RwLock;
+
+use crate::sync::Relaxed;
+
+type Hook = Box<dyn Fn(&LongHold) + Send + Sync>;
+
+// 1ms, far longer than any spinlock critical section should take
+static THRESHOLD: AtomicU64 = AtomicU64::new(1_000_000);
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+
+/// A lock that was held longer than the threshold.
+#[derive(Debug)]
+pub struct LongHold {
+    name: Option<&'static str>,
+    addr: *const (),
+    held: Duration,
+}
+
+impl LongHold {
+    /// The name from `SpinLock::new_named`, if it had one.
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    pub fn addr(&self) -> *const () {
+        self.addr
+    }
+
+    pub fn held(&self) -> Duration {
+        self.held
+    }
+}
+
+impl fmt::Display for LongHold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "spinlock {name:?}")?,
+            None => write!(f, "spinlock at {:?}", self.addr)?,
+        }
+        write!(f, " held for {:?}", self.held)
+    }
+}
+
+/// How long a lock may be held before it's reported. 1ms by default.
+pub fn set_threshold(threshold: Duration) {
+    THRESHOLD.store(threshold.as_nanos() as u64, Relaxed);
+}
+
+pub fn threshold() -> Duration {
+    Duration::from_nanos(THRESHOLD.load(Relaxed))
+}
+
+/// Replaces printing to stderr with `hook`. It runs on the thread that
+/// released the lock.
+pub fn set_hook<F: Fn(&LongHold) + Send + Sync + 'static>(hook: F) {
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(hook));
+}
+
+/// Goes back to printing to stderr.
+pub fn clear_hook() {
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+#[inline]
+pub(crate) fn released(name: Option<&'static str>, addr: *const (), held: Duration) {
+    if held.as_nanos() as u64 > THRESHOLD.load(Relaxed) {
+        report(LongHold { name, addr, held });
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn report(hold: LongHold) {
+    match &*HOOK.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(hook) => hook(&hold),
+        None => std::eprintln!("{hold}"),
+    }
+}
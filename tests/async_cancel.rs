@@ -0,0 +1,285 @@
+// A lock future that loses a select! must not keep a slot in the wait queue,
+// nor swallow a wakeup or a lock handoff it already received.
+#![cfg(feature = "alloc")]
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::channel::oneshot;
+use futures::executor::block_on;
+use futures::task::{waker, ArcWake};
+use futures::{select_biased, FutureExt};
+use spinlock::{AsyncCondvar, AsyncMutex, AsyncRwLock, AsyncSemaphore};
+
+#[derive(Default)]
+struct Flag(AtomicBool);
+
+impl ArcWake for Flag {
+    fn wake_by_ref(flag: &Arc<Self>) {
+        flag.0.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Flag {
+    fn woken(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+// Polls `fut` once with a waker that records whether it was woken.
+fn poll_with_flag<F: Future + Unpin>(fut: &mut F) -> (Arc<Flag>, Poll<F::Output>) {
+    let flag = Arc::new(Flag::default());
+    let waker = waker(flag.clone());
+    let poll = Pin::new(fut).poll(&mut Context::from_waker(&waker));
+    (flag, poll)
+}
+
+#[test]
+fn cancelled_mutex_waiter_leaves_queue() {
+    let lock = AsyncMutex::new(0);
+    let guard = lock.try_lock().unwrap();
+
+    block_on(async {
+        select_biased! {
+            _ = lock.lock().fuse() => panic!("lock is held"),
+            default => {}
+        }
+    });
+
+    let mut next = Box::pin(lock.lock());
+    let (flag, poll) = poll_with_flag(&mut next);
+    assert!(poll.is_pending());
+
+    // without cleanup this wakeup would go to the cancelled future
+    drop(guard);
+    assert!(flag.woken());
+    assert!(poll_with_flag(&mut next).1.is_ready());
+}
+
+#[test]
+fn cancelled_mutex_waiter_passes_on_wakeup() {
+    let lock = AsyncMutex::new(0);
+    let guard = lock.try_lock().unwrap();
+
+    block_on(async {
+        let mut first = Box::pin(lock.lock().fuse());
+        select_biased! {
+            _ = first => panic!("lock is held"),
+            default => {}
+        }
+
+        let mut second = Box::pin(lock.lock());
+        let (flag, poll) = poll_with_flag(&mut second);
+        assert!(poll.is_pending());
+
+        // wakes `first`, which is then cancelled without ever retrying
+        drop(guard);
+        assert!(!flag.woken());
+        drop(first);
+        assert!(flag.woken());
+        assert!(poll_with_flag(&mut second).1.is_ready());
+    });
+}
+
+#[test]
+fn cancelled_reader_returns_handed_over_lock() {
+    let lock = AsyncRwLock::new(0);
+    let writer = lock.try_write().unwrap();
+
+    block_on(async {
+        let mut reader = Box::pin(lock.read().fuse());
+        select_biased! {
+            _ = reader => panic!("write locked"),
+            default => {}
+        }
+        // hands the lock to the queued reader, which then goes away unused
+        drop(writer);
+        assert!(lock.try_write().is_none());
+        drop(reader);
+    });
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+fn cancelled_writer_returns_handed_over_lock() {
+    let lock = AsyncRwLock::new(0);
+    let reader = lock.try_read().unwrap();
+
+    block_on(async {
+        let mut writer = Box::pin(lock.write().fuse());
+        select_biased! {
+            _ = writer => panic!("read locked"),
+            default => {}
+        }
+        drop(reader);
+        assert!(lock.try_read().is_none());
+        drop(writer);
+    });
+    assert!(lock.try_read().is_some());
+}
+
+#[test]
+fn cancelled_writer_leaves_queue() {
+    let lock = AsyncRwLock::new(0);
+    let reader = lock.try_read().unwrap();
+
+    block_on(async {
+        select_biased! {
+            _ = lock.write().fuse() => panic!("read locked"),
+            default => {}
+        }
+    });
+    // a queued writer would keep new readers out
+    assert!(lock.try_read().is_some());
+    drop(reader);
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+fn cancelled_writer_releases_queued_readers() {
+    let lock = AsyncRwLock::new(0);
+    let reader = lock.try_read().unwrap();
+
+    let mut writer = Box::pin(lock.write());
+    assert!(poll_with_flag(&mut writer).1.is_pending());
+    // queues behind the writer rather than joining the first reader
+    let mut next = Box::pin(lock.read());
+    let (flag, poll) = poll_with_flag(&mut next);
+    assert!(poll.is_pending());
+
+    drop(writer);
+    assert!(flag.woken());
+    assert!(poll_with_flag(&mut next).1.is_ready());
+    drop(reader);
+}
+
+#[test]
+fn cancelled_condvar_waiter_passes_on_notify() {
+    let lock = AsyncMutex::new(0);
+    let condvar = AsyncCondvar::new();
+
+    let mut first = Box::pin(condvar.wait(lock.try_lock().unwrap()));
+    assert!(poll_with_flag(&mut first).1.is_pending());
+    let mut second = Box::pin(condvar.wait(lock.try_lock().unwrap()));
+    let (flag, poll) = poll_with_flag(&mut second);
+    assert!(poll.is_pending());
+
+    // picks `first`, which is then cancelled without ever retrying
+    condvar.notify_one();
+    assert!(!flag.woken());
+    drop(first);
+    assert!(flag.woken());
+    assert!(poll_with_flag(&mut second).1.is_ready());
+}
+
+#[test]
+fn cancelled_semaphore_waiter_unblocks_queue() {
+    let semaphore = AsyncSemaphore::new(2);
+    let held = semaphore.try_acquire().unwrap();
+
+    // waits for both permits, holding up the single one behind it
+    let mut many = Box::pin(semaphore.acquire_many(2));
+    assert!(poll_with_flag(&mut many).1.is_pending());
+    let mut one = Box::pin(semaphore.acquire());
+    let (flag, poll) = poll_with_flag(&mut one);
+    assert!(poll.is_pending());
+
+    drop(many);
+    assert!(flag.woken());
+    assert!(poll_with_flag(&mut one).1.is_ready());
+    drop(held);
+}
+
+#[test]
+fn cancelled_semaphore_waiter_returns_handed_over_permits() {
+    let semaphore = AsyncSemaphore::new(1);
+    let held = semaphore.try_acquire().unwrap();
+
+    let mut waiter = Box::pin(semaphore.acquire());
+    assert!(poll_with_flag(&mut waiter).1.is_pending());
+    // hands the permit to the waiter, which then goes away unused
+    drop(held);
+    assert_eq!(semaphore.available_permits(), 0);
+    drop(waiter);
+    assert_eq!(semaphore.available_permits(), 1);
+}
+
+// A deadline that passes whenever the test says so.
+fn deadline() -> (oneshot::Sender<()>, impl Future<Output = ()> + Unpin) {
+    let (send, recv) = oneshot::channel();
+    (send, recv.map(|_| ()))
+}
+
+#[test]
+fn timed_out_mutex_waiter_leaves_queue() {
+    let lock = AsyncMutex::new(0);
+    let guard = lock.try_lock().unwrap();
+
+    let (elapse, sleep) = deadline();
+    let mut timed = Box::pin(lock.lock_timeout(sleep));
+    assert!(poll_with_flag(&mut timed).1.is_pending());
+    let mut next = Box::pin(lock.lock());
+    let (flag, poll) = poll_with_flag(&mut next);
+    assert!(poll.is_pending());
+
+    elapse.send(()).unwrap();
+    assert!(matches!(poll_with_flag(&mut timed).1, Poll::Ready(None)));
+    // the timed out waiter is gone from the queue, even before it's dropped
+    drop(guard);
+    assert!(flag.woken());
+    assert!(poll_with_flag(&mut next).1.is_ready());
+    drop(timed);
+}
+
+#[test]
+fn timed_out_writer_returns_handed_over_lock() {
+    let lock = AsyncRwLock::new(0);
+    let reader = lock.try_read().unwrap();
+
+    let (elapse, sleep) = deadline();
+    let mut writer = Box::pin(lock.write_timeout(sleep));
+    assert!(poll_with_flag(&mut writer).1.is_pending());
+    // hands the lock to the writer, whose deadline passes before it looks
+    drop(reader);
+    elapse.send(()).unwrap();
+    let (_, poll) = poll_with_flag(&mut writer);
+    // polled first, so the handed over lock still counts
+    assert!(matches!(poll, Poll::Ready(Some(_))));
+    drop(poll);
+    assert!(lock.try_read().is_some());
+}
+
+#[test]
+fn timed_out_writer_releases_queued_readers() {
+    let lock = AsyncRwLock::new(0);
+    let reader = lock.try_read().unwrap();
+
+    let (elapse, sleep) = deadline();
+    let mut writer = Box::pin(lock.write_timeout(sleep));
+    assert!(poll_with_flag(&mut writer).1.is_pending());
+    let mut next = Box::pin(lock.read());
+    let (flag, poll) = poll_with_flag(&mut next);
+    assert!(poll.is_pending());
+
+    elapse.send(()).unwrap();
+    assert!(matches!(poll_with_flag(&mut writer).1, Poll::Ready(None)));
+    assert!(flag.woken());
+    assert!(poll_with_flag(&mut next).1.is_ready());
+    drop(reader);
+    drop(writer);
+}
+
+#[test]
+fn timed_out_reader_after_deadline_takes_no_lock() {
+    let lock = AsyncRwLock::new(0);
+    let writer = lock.try_write().unwrap();
+
+    block_on(async {
+        assert!(lock.read_timeout(async {}).await.is_none());
+    });
+    drop(writer);
+    assert!(lock.try_write().is_some());
+}
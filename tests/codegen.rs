@@ -0,0 +1,96 @@
+//! Checks the uncontended paths in examples/fast_paths.rs compile to a
+//! handful of instructions, and that anything they reach out of line is a
+//! slow path. It builds the crate again in release, so it's opt-in:
+//!
+//! ```text
+//! cargo test --release --test codegen -- --ignored
+//! ```
+#![cfg(all(
+    not(any(loom, shuttle)),
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// Generous next to what they take today (the RwSpinLock ones dispatch on
+// the policy), tight next to a spin loop inlined by accident.
+const MAX_INSTRUCTIONS: usize = 48;
+
+const FAST_PATHS: [&str; 9] = [
+    "spin_lock",
+    "spin_try_lock",
+    "ticket_lock",
+    "rw_read",
+    "rw_write",
+    "phase_fair_read",
+    "phase_fair_write",
+    "seqlock_read",
+    "semaphore_acquire",
+];
+
+#[test]
+#[ignore]
+fn fast_paths_stay_small() {
+    let target = Path::new(env!("CARGO_TARGET_TMPDIR")).join("codegen");
+    let examples = target.join("release").join("examples");
+    // so only this build's assembly is left to find
+    let _ = fs::remove_dir_all(&examples);
+    let status = Command::new(env!("CARGO"))
+        .args([
+            "rustc",
+            "--release",
+            "--example",
+            "fast_paths",
+            "--target-dir",
+        ])
+        .arg(&target)
+        .args(["--", "--emit", "asm"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let asm = fs::read_dir(&examples)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.starts_with("fast_paths-") && name.ends_with(".s")
+        })
+        .map(|path| fs::read_to_string(path).unwrap())
+        .unwrap();
+
+    for name in FAST_PATHS {
+        let body = function(&asm, name);
+        let instructions: Vec<&str> = body
+            .lines()
+            .filter(|line| line.starts_with('\t') && !line.starts_with("\t."))
+            .collect();
+        assert!(
+            instructions.len() <= MAX_INSTRUCTIONS,
+            "{name} is {} instructions:\n{body}",
+            instructions.len(),
+        );
+        // calls, tail calls and GOT loads all name their target
+        for line in instructions {
+            if let Some(at) = line.find("_ZN") {
+                let callee = &line[at..];
+                assert!(
+                    callee.contains("slow") || callee.contains("wait"),
+                    "{name} reaches {callee} out of line:\n{body}",
+                );
+            }
+        }
+    }
+}
+
+// From the function's label to the end of its unwind info.
+fn function<'a>(asm: &'a str, name: &str) -> &'a str {
+    let start = asm
+        .find(&format!("\n{name}:\n"))
+        .unwrap_or_else(|| panic!("no {name} in the assembly"));
+    let body = &asm[start + 1..];
+    &body[..body.find(".cfi_endproc").unwrap()]
+}
@@ -0,0 +1,123 @@
+//! Model-checks the core lock under every interleaving loom can find. Run
+//! with
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --release --test loom
+//! ```
+//!
+//! The values are loom `UnsafeCell`s, so an access that isn't ordered after
+//! the previous holder's by the lock fails the model.
+#![cfg(loom)]
+
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+use loom::cell::UnsafeCell;
+use loom::sync::Arc;
+use loom::thread;
+use spinlock::{Guard, SpinLock};
+
+// Loom creates the lock's state on first use, which has to happen before
+// other threads can see it, see src/sync.rs.
+fn new_lock() -> Arc<SpinLock<UnsafeCell<usize>>> {
+    let lock = Arc::new(SpinLock::new(UnsafeCell::new(0)));
+    assert!(!lock.is_locked());
+    lock
+}
+
+fn increment(lock: &SpinLock<UnsafeCell<usize>>) {
+    let guard = lock.lock();
+    guard.with_mut(|value| unsafe { *value += 1 });
+}
+
+#[test]
+fn lock_excludes() {
+    loom::model(|| {
+        let lock = new_lock();
+        let other = {
+            let lock = lock.clone();
+            thread::spawn(move || increment(&lock))
+        };
+        increment(&lock);
+        other.join().unwrap();
+        let guard = lock.lock();
+        assert_eq!(guard.with(|value| unsafe { *value }), 2);
+    });
+}
+
+#[test]
+fn try_lock_sees_unlocked_writes() {
+    loom::model(|| {
+        let lock = new_lock();
+        let other = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                if let Some(guard) = lock.try_lock() {
+                    guard.with_mut(|value| unsafe { *value += 1 });
+                }
+            })
+        };
+        if let Some(guard) = lock.try_lock() {
+            guard.with_mut(|value| unsafe { *value += 1 });
+        }
+        other.join().unwrap();
+        let value = lock.lock().with(|value| unsafe { *value });
+        assert!(value == 1 || value == 2);
+    });
+}
+
+#[test]
+fn try_lock_fails_while_held() {
+    loom::model(|| {
+        let lock = new_lock();
+        let guard = lock.lock();
+        let other = {
+            let lock = lock.clone();
+            thread::spawn(move || assert!(lock.try_lock().is_none()))
+        };
+        other.join().unwrap();
+        drop(guard);
+        assert!(lock.try_lock().is_some());
+    });
+}
+
+#[test]
+fn bump_and_unlocked_relock() {
+    loom::model(|| {
+        let lock = new_lock();
+        let other = {
+            let lock = lock.clone();
+            thread::spawn(move || increment(&lock))
+        };
+        let mut guard = lock.lock();
+        guard.with_mut(|value| unsafe { *value += 1 });
+        Guard::bump(&mut guard);
+        guard.with_mut(|value| unsafe { *value += 1 });
+        Guard::unlocked(&mut guard, || {});
+        guard.with_mut(|value| unsafe { *value += 1 });
+        drop(guard);
+        other.join().unwrap();
+        assert_eq!(lock.lock().with(|value| unsafe { *value }), 4);
+    });
+}
+
+#[test]
+fn lock_with_cancel_gives_up() {
+    loom::model(|| {
+        let lock = new_lock();
+        // not modelled, the lock's own spinning is what lets loom switch
+        let cancel = Arc::new(AtomicBool::new(false));
+        let guard = lock.lock();
+        let other = {
+            let (lock, cancel) = (lock.clone(), cancel.clone());
+            thread::spawn(move || {
+                if let Some(guard) = lock.lock_with_cancel(&cancel) {
+                    guard.with_mut(|value| unsafe { *value += 1 });
+                }
+            })
+        };
+        guard.with_mut(|value| unsafe { *value += 1 });
+        cancel.store(true, Relaxed);
+        drop(guard);
+        other.join().unwrap();
+    });
+}
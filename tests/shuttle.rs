@@ -0,0 +1,278 @@
+//! Runs the locks under shuttle's randomized and PCT schedulers, thousands
+//! of schedules each, with
+//!
+//! ```text
+//! RUSTFLAGS="--cfg shuttle" cargo test --release --test shuttle
+//! ```
+//!
+//! Loom checks every interleaving of tiny cases; these go for more threads
+//! and longer runs. A schedule that doesn't finish within `MAX_STEPS` fails,
+//! which is how a starved waiter or a lost wakeup shows up.
+#![cfg(shuttle)]
+
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+
+use shuttle::scheduler::{PctScheduler, RandomScheduler};
+use shuttle::sync::atomic::AtomicBool;
+use shuttle::{thread, Config, MaxSteps, Runner};
+use spinlock::{
+    ClhLock, CohortLock, McsLock, McsNode, PhaseFairRwLock, RwPolicy, RwSpinLock, SpinCondvar,
+    SpinLock, TicketGuard, TicketLock,
+};
+
+const ITERATIONS: usize = 1000;
+const MAX_STEPS: usize = 100_000;
+const THREADS: usize = 3;
+
+fn check<F: Fn() + Send + Sync + 'static>(f: F) {
+    let config = || {
+        let mut config = Config::new();
+        config.max_steps = MaxSteps::FailAfter(MAX_STEPS);
+        config
+    };
+    let f = Arc::new(f);
+    let random = f.clone();
+    Runner::new(RandomScheduler::new(ITERATIONS), config()).run(move || random());
+    Runner::new(PctScheduler::new(3, ITERATIONS), config()).run(move || f());
+}
+
+// Runs `f(i)` on THREADS threads and waits for all of them.
+fn on_threads<F: Fn(usize) + Send + Sync + 'static>(f: F) {
+    let f = Arc::new(f);
+    let threads: Vec<_> = (0..THREADS)
+        .map(|i| {
+            let f = f.clone();
+            thread::spawn(move || f(i))
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+// Marks a critical section, failing if two threads are ever in it at once.
+// Yields in the middle to give the scheduler a chance to try.
+#[derive(Default)]
+struct Exclusive(AtomicBool);
+
+impl Exclusive {
+    fn enter(&self) {
+        assert!(!self.0.swap(true, SeqCst), "two holders at once");
+        thread::yield_now();
+        self.0.store(false, SeqCst);
+    }
+}
+
+#[test]
+fn spinlock() {
+    check(|| {
+        let lock = Arc::new((SpinLock::new(0), Exclusive::default()));
+        let shared = lock.clone();
+        on_threads(move |_| {
+            let (lock, exclusive) = &*shared;
+            for _ in 0..2 {
+                let mut guard = lock.lock();
+                exclusive.enter();
+                *guard += 1;
+            }
+            if let Some(mut guard) = lock.try_lock() {
+                exclusive.enter();
+                *guard += 1;
+                *guard -= 1;
+            }
+        });
+        assert_eq!(*lock.0.lock(), 2 * THREADS);
+    });
+}
+
+#[test]
+fn ticket() {
+    check(|| {
+        let lock = Arc::new((TicketLock::new(0), Exclusive::default()));
+        let shared = lock.clone();
+        on_threads(move |_| {
+            let (lock, exclusive) = &*shared;
+            let mut guard = lock.lock();
+            exclusive.enter();
+            *guard += 1;
+            TicketGuard::bump(&mut guard);
+            exclusive.enter();
+            *guard += 1;
+        });
+        assert_eq!(*lock.0.lock(), 2 * THREADS);
+    });
+}
+
+#[test]
+fn mcs() {
+    check(|| {
+        let lock = Arc::new((McsLock::new(0), Exclusive::default()));
+        let shared = lock.clone();
+        on_threads(move |_| {
+            let (lock, exclusive) = &*shared;
+            for _ in 0..2 {
+                let mut node = McsNode::new();
+                let mut guard = lock.lock(&mut node);
+                exclusive.enter();
+                *guard += 1;
+            }
+        });
+        assert_eq!(*lock.0.lock(&mut McsNode::new()), 2 * THREADS);
+    });
+}
+
+#[test]
+fn clh() {
+    check(|| {
+        let lock = Arc::new((ClhLock::new(0), Exclusive::default()));
+        let shared = lock.clone();
+        on_threads(move |_| {
+            let (lock, exclusive) = &*shared;
+            for _ in 0..2 {
+                let mut guard = lock.lock();
+                exclusive.enter();
+                *guard += 1;
+            }
+        });
+        assert_eq!(*lock.0.lock(), 2 * THREADS);
+    });
+}
+
+// Threads on two nodes, so the global lock is both passed within a cohort
+// and handed between them.
+#[test]
+fn cohort() {
+    check(|| {
+        let lock = Arc::new((CohortLock::new(0), Exclusive::default()));
+        let shared = lock.clone();
+        on_threads(move |i| {
+            let (lock, exclusive) = &*shared;
+            for _ in 0..2 {
+                let mut guard = lock.lock_on(i % 2);
+                exclusive.enter();
+                *guard += 1;
+            }
+            if let Some(mut guard) = lock.try_lock() {
+                exclusive.enter();
+                *guard += 1;
+            }
+        });
+        assert!(*lock.0.lock() >= 2 * THREADS);
+    });
+}
+
+// One writer, one upgrader and a reader, with the writers keeping both
+// halves of the pair equal.
+fn rwlock(policy: RwPolicy) {
+    check(move || {
+        let lock = Arc::new(RwSpinLock::with_policy((0, 0), policy));
+        let shared = lock.clone();
+        on_threads(move |i| match i {
+            0 => {
+                let mut guard = shared.write();
+                guard.0 += 1;
+                thread::yield_now();
+                guard.1 += 1;
+                let guard = guard.downgrade();
+                assert_eq!(guard.0, guard.1);
+            }
+            1 => {
+                let guard = shared.upgradeable_read();
+                assert_eq!(guard.0, guard.1);
+                let mut guard = guard.upgrade();
+                guard.0 += 1;
+                thread::yield_now();
+                guard.1 += 1;
+            }
+            _ => {
+                for _ in 0..2 {
+                    let guard = shared.read();
+                    thread::yield_now();
+                    assert_eq!(guard.0, guard.1);
+                }
+            }
+        });
+        assert_eq!(*lock.read(), (2, 2));
+    });
+}
+
+#[test]
+fn rwlock_reader_preferred() {
+    rwlock(RwPolicy::ReaderPreferred);
+}
+
+#[test]
+fn rwlock_writer_preferred() {
+    rwlock(RwPolicy::WriterPreferred);
+}
+
+#[test]
+fn rwlock_fair() {
+    rwlock(RwPolicy::Fair);
+}
+
+#[test]
+fn phase_fair() {
+    check(|| {
+        let lock = Arc::new(PhaseFairRwLock::new((0, 0)));
+        let shared = lock.clone();
+        on_threads(move |i| {
+            if i == 0 {
+                for _ in 0..2 {
+                    let mut guard = shared.write();
+                    guard.0 += 1;
+                    thread::yield_now();
+                    guard.1 += 1;
+                }
+            } else {
+                for _ in 0..2 {
+                    let guard = shared.read();
+                    thread::yield_now();
+                    assert_eq!(guard.0, guard.1);
+                }
+            }
+        });
+        assert_eq!(*lock.read(), (2, 2));
+    });
+}
+
+// Every waiter has to wake up for its item, however the notifications and
+// waits interleave.
+#[test]
+fn condvar_notify_one() {
+    check(|| {
+        let state = Arc::new((SpinLock::new(0), SpinCondvar::new()));
+        let shared = state.clone();
+        on_threads(move |i| {
+            let (lock, condvar) = &*shared;
+            if i == 0 {
+                for _ in 0..THREADS - 1 {
+                    *lock.lock() += 1;
+                    condvar.notify_one();
+                }
+            } else {
+                let mut guard = condvar.wait_while(lock.lock(), |items| *items == 0);
+                *guard -= 1;
+            }
+        });
+        assert_eq!(*state.0.lock(), 0);
+    });
+}
+
+#[test]
+fn condvar_notify_all() {
+    check(|| {
+        let state = Arc::new((SpinLock::new(false), SpinCondvar::new()));
+        let shared = state.clone();
+        on_threads(move |i| {
+            let (lock, condvar) = &*shared;
+            if i == 0 {
+                *lock.lock() = true;
+                condvar.notify_all();
+            } else {
+                drop(condvar.wait_while(lock.lock(), |ready| !*ready));
+            }
+        });
+    });
+}
@@ -0,0 +1,106 @@
+//! Every primitive that can be built in a `const` context, built in a
+//! `static`, so a constructor that stops being `const fn` fails to compile
+//! here. Those that can't be: `RcuCell` (allocates), `SpinMap` and
+//! `KeyedLock` (randomly seeded hashers) and `ShardedSpinLock`; put those in
+//! a `Lazy`.
+//!
+//! The model checkers' atomics aren't `const`, so none of this builds under
+//! them.
+#![cfg(not(any(loom, shuttle)))]
+// Building them is the test, not all of them get used.
+#![allow(dead_code)]
+
+use spinlock::{
+    AtomicCell, Backoff, CachePadded, CohortLock, Event, HierarchicalSpinLock, IrqSpinLock, Latch,
+    Lazy, Level1, McsLock, OrderedSpinLock, PhaseFairRwLock, PriorityLock, RawRwSpinLock,
+    RawSpinLock, RwPolicy, RwSpinLock, SeqCount, SeqLock, Spin, SpinBarrier, SpinCondvar, SpinLock,
+    SpinOnce, SpinQueue, SpinSemaphore, TicketLock, WaitGroup,
+};
+
+static LOCK: SpinLock<u32> = SpinLock::new(0);
+static NAMED: SpinLock<u32> = SpinLock::new_named("counter", 0);
+static SPINNING: SpinLock<u32, Spin> = SpinLock::with_relax(0);
+static RAW: RawSpinLock = RawSpinLock::new();
+static TICKET: TicketLock<u32> = TicketLock::new(0);
+static RW: RwSpinLock<u32> = RwSpinLock::new(0);
+static WRITER_FIRST: RwSpinLock<u32> = RwSpinLock::with_policy(0, RwPolicy::WriterPreferred);
+static RAW_RW: RawRwSpinLock = RawRwSpinLock::new();
+static PHASE_FAIR: PhaseFairRwLock<u32> = PhaseFairRwLock::new(0);
+static MCS: McsLock<u32> = McsLock::new(0);
+static COHORT: CohortLock<u32> = CohortLock::new(0);
+static PRIORITY: PriorityLock<u32> = PriorityLock::new(0);
+static HIERARCHICAL: HierarchicalSpinLock<u32> = HierarchicalSpinLock::new(1, 0);
+static ORDERED: OrderedSpinLock<u32, Level1> = OrderedSpinLock::new(0);
+static SEQLOCK: SeqLock<u32> = SeqLock::new(0);
+static SEQCOUNT: SeqCount = SeqCount::new();
+static ONCE: SpinOnce<u32> = SpinOnce::new();
+static LAZY: Lazy<u32> = Lazy::new(|| 7);
+static SEMAPHORE: SpinSemaphore = SpinSemaphore::new(2);
+static BARRIER: SpinBarrier = SpinBarrier::new(1);
+static LATCH: Latch = Latch::new(1);
+static WAIT_GROUP: WaitGroup = WaitGroup::new();
+static EVENT: Event = Event::new();
+static CONDVAR: SpinCondvar = SpinCondvar::new();
+static QUEUE: SpinQueue<u32, 4> = SpinQueue::new();
+static CELL: AtomicCell<u64> = AtomicCell::new(0);
+static PADDED: CachePadded<SpinLock<u32>> = CachePadded::new(SpinLock::new(0));
+static BACKOFF: SpinLock<Backoff> = SpinLock::new(Backoff::new());
+
+// IrqSpinLock::new has no bound on the interrupt controller.
+struct NoInterrupts;
+static IRQ: IrqSpinLock<u32, NoInterrupts> = IrqSpinLock::new(0);
+
+#[cfg(feature = "std")]
+mod std_only {
+    use spinlock::{BravoRwLock, HybridMutex, ReentrantSpinLock};
+
+    pub static BRAVO: BravoRwLock<u32> = BravoRwLock::new(0);
+    pub static HYBRID: HybridMutex<u32> = HybridMutex::new(0);
+    pub static REENTRANT: ReentrantSpinLock<u32> = ReentrantSpinLock::new(0);
+}
+
+#[cfg(feature = "alloc")]
+mod alloc_only {
+    use spinlock::{AsyncCondvar, AsyncMutex, AsyncRwLock, AsyncSemaphore, ClhLock};
+
+    pub static ASYNC_MUTEX: AsyncMutex<u32> = AsyncMutex::new(0);
+    pub static ASYNC_RW: AsyncRwLock<u32> = AsyncRwLock::new(0);
+    pub static ASYNC_SEMAPHORE: AsyncSemaphore = AsyncSemaphore::new(1);
+    pub static ASYNC_CONDVAR: AsyncCondvar = AsyncCondvar::new();
+    pub static CLH: ClhLock<u32> = ClhLock::new(0);
+}
+
+#[cfg(feature = "poison")]
+static POISON: spinlock::PoisonSpinLock<u32> = spinlock::PoisonSpinLock::new(0);
+
+#[cfg(feature = "os-wait")]
+static FUTEX: spinlock::FutexLock<u32> = spinlock::FutexLock::new(0);
+
+#[test]
+fn statics_lock() {
+    *LOCK.lock() += 1;
+    *NAMED.lock() += 1;
+    *SPINNING.lock() += 1;
+    RAW.lock();
+    // safety: locked just above
+    unsafe { RAW.unlock() };
+    *TICKET.lock() += 1;
+    *RW.write() += 1;
+    *WRITER_FIRST.write() += 1;
+    *PHASE_FAIR.write() += 1;
+    *COHORT.lock() += 1;
+    *HIERARCHICAL.lock() += 1;
+    *SEQLOCK.write() = 1;
+    assert_eq!(SEQLOCK.read(), 1);
+    assert_eq!(*ONCE.get_or_init(|| 1), 1);
+    assert_eq!(*LAZY, 7);
+    assert!(SEMAPHORE.try_acquire().is_some());
+    BARRIER.wait();
+    LATCH.count_down();
+    LATCH.wait();
+    WAIT_GROUP.wait();
+    QUEUE.push(1);
+    CELL.store(1);
+    *PADDED.lock() += 1;
+    BACKOFF.lock().spin();
+}